@@ -1,82 +1,1369 @@
-use crate::scenes::game;
-use crate::scenes::game::Buttons;
+use crate::layout::Layout;
+use crate::variations::Variations;
+use crate::zobrist;
+use std::ops::{Add, Mul};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Team {
     Empty,
     White,
     Black,
+    Red,
+    Green,
 }
 impl Team {
+    /// All the teams that can play, in the default turn order.
+    pub const PLAYERS: [Team; MAX_TEAMS] = [Team::White, Team::Black, Team::Red, Team::Green];
+
+    /// The other team of a two-team game.
     #[must_use]
     pub fn toggle(&self) -> Team {
         match self {
             Team::White => Team::Black,
             Team::Black => Team::White,
-            _ => panic!("can not toggle a Team::Empty"),
+            _ => panic!(
+                "can only toggle Team::White and Team::Black, got {:?}",
+                self
+            ),
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Team::Empty => "empty",
+            Team::White => "white",
+            Team::Black => "black",
+            Team::Red => "red",
+            Team::Green => "green",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Team> {
+        Self::PLAYERS.iter().copied().find(|t| t.name() == name)
+    }
+}
+
+pub const MAX_TEAMS: usize = 4;
+
+/// The teams that play, in the order they move. The first one starts.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TurnOrder {
+    teams: [Team; MAX_TEAMS],
+    count: usize,
+}
+impl TurnOrder {
+    /// Returns None unless there are between 2 and [`MAX_TEAMS`] different playing teams.
+    pub fn new(teams: &[Team]) -> Option<Self> {
+        let valid_count = (2..=MAX_TEAMS).contains(&teams.len());
+        let all_playing = teams.iter().all(|team| *team != Team::Empty);
+        let all_different = (1..teams.len()).all(|i| !teams[..i].contains(&teams[i]));
+        if !(valid_count && all_playing && all_different) {
+            return None;
+        }
+        let mut padded = [Team::Empty; MAX_TEAMS];
+        padded[..teams.len()].copy_from_slice(teams);
+        Some(Self {
+            teams: padded,
+            count: teams.len(),
+        })
+    }
+    /// The first `count` teams of [`Team::PLAYERS`].
+    pub fn with_count(count: usize) -> Option<Self> {
+        Self::new(Team::PLAYERS.get(..count)?)
+    }
+    pub fn teams(&self) -> &[Team] {
+        &self.teams[..self.count]
+    }
+    pub fn first(&self) -> Team {
+        self.teams[0]
+    }
+    /// The team that moves after this one. A team that is not playing, like the turn of a
+    /// pasted position with more teams, is followed by the first team.
+    pub fn after(&self, team: Team) -> Team {
+        match self.teams().iter().position(|t| *t == team) {
+            Some(index) => self.teams[(index + 1) % self.count],
+            None => self.first(),
+        }
+    }
+    pub fn name(&self) -> String {
+        let names = self.teams().iter().map(|team| team.name());
+        names.collect::<Vec<_>>().join(", ")
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        let teams = name
+            .split(',')
+            .map(|team| Team::from_name(team.trim()))
+            .collect::<Option<Vec<_>>>()?;
+        Self::new(&teams)
+    }
+    /// The next ordering of the same teams, cycling through all of them.
+    #[must_use]
+    pub fn next_permutation(&self) -> Self {
+        let index = |team: &Team| Team::PLAYERS.iter().position(|t| t == team).unwrap();
+        let mut indexes = self.teams().iter().map(index).collect::<Vec<_>>();
+        // the usual lexicographic next permutation, wrapping to the first one at the end
+        match (1..indexes.len())
+            .rev()
+            .find(|i| indexes[i - 1] < indexes[*i])
+        {
+            Some(i) => {
+                let j = (i..indexes.len())
+                    .rev()
+                    .find(|j| indexes[*j] > indexes[i - 1])
+                    .unwrap();
+                indexes.swap(i - 1, j);
+                indexes[i..].reverse();
+            }
+            None => indexes.reverse(),
+        }
+        let teams = indexes
+            .iter()
+            .map(|i| Team::PLAYERS[*i])
+            .collect::<Vec<_>>();
+        Self::new(&teams).unwrap()
+    }
+}
+impl Default for TurnOrder {
+    fn default() -> Self {
+        Self::with_count(2).unwrap()
+    }
+}
+
+/// A cell of the board. `x` is the column and `y` is the row.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Coord {
+    pub x: i32,
+    pub y: i32,
+}
+impl Coord {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+impl Add for Coord {
+    type Output = Coord;
+    fn add(self, rhs: Coord) -> Coord {
+        Coord::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+impl Mul<i32> for Coord {
+    type Output = Coord;
+    fn mul(self, rhs: i32) -> Coord {
+        Coord::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Move {
+    /// Place a stone with the polarity that the rules impose.
+    Place(Coord),
+    /// Place a stone with the chosen polarity, when the rules let the player choose.
+    PlacePolarized(Coord, Polarity),
+    /// Skip the turn without placing a stone.
+    Pass,
+    /// Take over the first player's stones instead of placing one, with the pie rule.
+    Swap,
+    /// Move a stone of the team in turn, once no more stones can be placed. See
+    /// [`Rules::relocation`].
+    Relocate { from: Coord, to: Coord },
+}
+impl Move {
+    /// Where the stone is placed or lands, or None for a pass.
+    pub fn coord(&self) -> Option<Coord> {
+        match self {
+            Move::Place(coord) | Move::PlacePolarized(coord, _) => Some(*coord),
+            Move::Relocate { to, .. } => Some(*to),
+            Move::Pass | Move::Swap => None,
+        }
+    }
+}
+
+/// Shorthand for the tests of several modules.
+#[cfg(test)]
+pub fn place(x: i32, y: i32) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Polarity {
+    Attract,
+    Repel,
+}
+impl Polarity {
+    #[must_use]
+    pub fn opposite(&self) -> Polarity {
+        match self {
+            Polarity::Attract => Polarity::Repel,
+            Polarity::Repel => Polarity::Attract,
+        }
+    }
+}
+
+/// A stone of an opponent that was pulled next to the new stone, or pushed away from it, and
+/// became of the mover's team.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Pull {
+    pub from: Coord,
+    pub to: Coord,
+    /// The team the stone had before the move.
+    pub team: Team,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct MoveOutcome {
+    pub team: Team,
+    /// None for a pass.
+    pub placed: Option<Coord>,
+    pub pulls: Vec<Pull>,
+    /// How many turns in a row had been passed before the move.
+    pub previous_passes: usize,
+    /// Whether the move swapped the colours of the stones, see [`Move::Swap`].
+    pub swapped: bool,
+    /// Whether the team had already placed a stone in this turn before the move.
+    pub second_placement: bool,
+    /// Where the stone came from, for a [`Move::Relocate`].
+    pub lifted: Option<Coord>,
+    /// The cells that the previous move vacated with its pulls.
+    pub previous_vacated: Vec<Coord>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    Win(Team),
+    /// Several teams share the highest score.
+    Draw,
+    /// Contains the team that resigned.
+    Resignation(Team),
+}
+
+/// Implements `ALL`, `name`, `from_name` and `next` for an enum of rule options, where each
+/// variant has a fixed name and `next` cycles through them in the given order.
+macro_rules! named_options {
+    ($options:ident { $($variant:ident => $name:literal),+ $(,)? }) => {
+        impl $options {
+            pub const ALL: &'static [$options] = &[$($options::$variant),+];
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($options::$variant => $name,)+
+                }
+            }
+            pub fn from_name(name: &str) -> Option<$options> {
+                Self::ALL.iter().copied().find(|option| option.name() == name)
+            }
+            #[must_use]
+            pub fn next(&self) -> $options {
+                let index = Self::ALL.iter().position(|option| option == self).unwrap();
+                Self::ALL[(index + 1) % Self::ALL.len()]
+            }
+        }
+    };
+}
+
+/// How the edges of the board are glued together.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Topology {
+    Torus,
+    /// No wrapping at all.
+    Plane,
+    /// The left and right edges are glued, so rows wrap around.
+    HorizontalCylinder,
+    /// The top and bottom edges are glued, so columns wrap around.
+    VerticalCylinder,
+    /// Like a torus, but crossing the left or right edge mirrors the row.
+    KleinBottle,
+    /// Crossing the left or right edge mirrors the row, and crossing the top or bottom edge
+    /// mirrors the column.
+    ProjectivePlane,
+    /// Hexagonal cells glued like a torus. The cells form a rhombus where each row is shifted
+    /// half a cell to the right of the row above, and the stones pull along the 3 hex axes
+    /// whatever the magnetism.
+    HexagonalTorus,
+}
+named_options!(Topology {
+    Torus => "torus",
+    Plane => "plane",
+    HorizontalCylinder => "horizontal cylinder",
+    VerticalCylinder => "vertical cylinder",
+    KleinBottle => "Klein bottle",
+    ProjectivePlane => "projective plane",
+    HexagonalTorus => "hexagonal torus",
+});
+impl Topology {
+    pub fn is_hexagonal(&self) -> bool {
+        matches!(self, Topology::HexagonalTorus)
+    }
+    fn wraps_x(&self) -> bool {
+        !matches!(self, Topology::Plane | Topology::VerticalCylinder)
+    }
+    fn wraps_y(&self) -> bool {
+        !matches!(self, Topology::Plane | Topology::HorizontalCylinder)
+    }
+    fn mirrors_when_wrapping_x(&self) -> bool {
+        matches!(self, Topology::KleinBottle | Topology::ProjectivePlane)
+    }
+    fn mirrors_when_wrapping_y(&self) -> bool {
+        matches!(self, Topology::ProjectivePlane)
+    }
+}
+
+/// How far a new stone pulls, counting the cells from the new stone to the pulled one.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Reach {
+    /// Up to the length of the axis, which on a wrapping board is the whole loop.
+    Unlimited,
+    Cells(i32),
+}
+impl Reach {
+    /// The adjacent cell must be empty to be pulled into, so nothing closer can be pulled.
+    pub const MIN_CELLS: i32 = 2;
+    const MAX_CELLS_IN_CYCLE: i32 = 5;
+
+    pub fn name(&self) -> String {
+        match self {
+            Reach::Unlimited => "unlimited".to_string(),
+            Reach::Cells(cells) => cells.to_string(),
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Reach> {
+        match name {
+            "unlimited" => Some(Reach::Unlimited),
+            _ => name
+                .parse()
+                .ok()
+                .filter(|cells| *cells >= Self::MIN_CELLS)
+                .map(Reach::Cells),
+        }
+    }
+    #[must_use]
+    pub fn next(&self) -> Reach {
+        match self {
+            Reach::Unlimited => Reach::Cells(Self::MIN_CELLS),
+            Reach::Cells(cells) if *cells >= Self::MAX_CELLS_IN_CYCLE => Reach::Unlimited,
+            Reach::Cells(cells) => Reach::Cells(cells + 1),
+        }
+    }
+    /// The furthest distance that a stone can be pulled from, along an axis of this length.
+    pub fn max_distance(&self, axis_length: i32) -> i32 {
+        match self {
+            Reach::Unlimited => axis_length - 1,
+            Reach::Cells(cells) => (*cells).min(axis_length - 1),
+        }
+    }
+}
+
+/// How many stones each team places in its turn.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum StonesPerTurn {
+    One,
+    /// Two stones in every turn but the first one of the game, as in connect6. Each stone pulls
+    /// as soon as it's placed, before the second one.
+    Two,
+}
+named_options!(StonesPerTurn {
+    One => "one",
+    Two => "two",
+});
+
+/// How a team wins, besides the opponents resigning.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WinCondition {
+    /// The most stones once the board is full or every team passes.
+    Majority,
+    /// The first team to have this many stones on the board.
+    Stones(i32),
+    /// The first team to have this many stones in a row or column, which can wrap around.
+    Line(i32),
+}
+impl WinCondition {
+    const STONES_IN_CYCLE: [i32; 3] = [10, 15, 20];
+    const LINES_IN_CYCLE: [i32; 3] = [3, 4, 5];
+
+    pub fn name(&self) -> String {
+        match self {
+            WinCondition::Majority => "majority".to_string(),
+            WinCondition::Stones(stones) => format!("{} stones", stones),
+            WinCondition::Line(length) => format!("line of {}", length),
         }
     }
-    pub fn choose<T>(&self, if_empty: T, if_white: T, if_black: T) -> T {
+    pub fn from_name(name: &str) -> Option<WinCondition> {
+        let positive = |number: &str| number.parse().ok().filter(|number| *number > 0);
+        if name == "majority" {
+            Some(WinCondition::Majority)
+        } else if let Some(stones) = name.strip_suffix(" stones") {
+            positive(stones).map(WinCondition::Stones)
+        } else {
+            positive(name.strip_prefix("line of ")?).map(WinCondition::Line)
+        }
+    }
+    #[must_use]
+    pub fn next(&self) -> WinCondition {
+        let cycle = Self::STONES_IN_CYCLE
+            .map(WinCondition::Stones)
+            .into_iter()
+            .chain(Self::LINES_IN_CYCLE.map(WinCondition::Line))
+            .chain([WinCondition::Majority]);
+        let mut after_self = cycle.skip_while(|condition| condition != self).skip(1);
+        after_self
+            .next()
+            .unwrap_or(WinCondition::Stones(Self::STONES_IN_CYCLE[0]))
+    }
+}
+
+/// The directions in which a new stone pulls.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Magnetism {
+    /// Rows and columns, the original orthomagnet.
+    Orthogonal,
+    /// Only the diagonals.
+    Diagonal,
+    /// Rows, columns and diagonals.
+    Omnidirectional,
+}
+named_options!(Magnetism {
+    Orthogonal => "orthomagnet",
+    Diagonal => "diagomagnet",
+    Omnidirectional => "omnimagnet",
+});
+impl Magnetism {
+    fn axes(&self) -> &'static [(Coord, Coord)] {
         match self {
-            Team::Empty => if_empty,
-            Team::White => if_white,
-            Team::Black => if_black,
+            Magnetism::Orthogonal => &ORTHOGONAL_AXES,
+            Magnetism::Diagonal => &DIAGONAL_AXES,
+            Magnetism::Omnidirectional => &ALL_AXES,
+        }
+    }
+}
+
+/// Whether new stones attract or repel the opponent stones.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PolarityRule {
+    Attract,
+    Repel,
+    /// The player chooses the polarity of each stone.
+    PlayerChoice,
+}
+named_options!(PolarityRule {
+    Attract => "attract",
+    Repel => "repel",
+    PlayerChoice => "player choice",
+});
+
+/// What happens when a move recreates an earlier position of the game. Placing a stone always
+/// adds one to the board, so only moves that don't place stones can repeat a position.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Repetition {
+    Allowed,
+    /// Moves that recreate an earlier position are illegal.
+    Superko,
+    /// Recreating an earlier position ends the game in a draw.
+    Draw,
+}
+named_options!(Repetition {
+    Allowed => "allowed",
+    Superko => "superko",
+    Draw => "draw",
+});
+
+/// The variant of the game being played, besides the board shape.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Rules {
+    pub reach: Reach,
+    pub magnetism: Magnetism,
+    pub polarity: PolarityRule,
+    pub turn_order: TurnOrder,
+    pub repetition: Repetition,
+    /// Whether the second player may take over the first stone instead of moving, so that the
+    /// first player has a reason to open with a fair move. Only with two players.
+    pub pie: bool,
+    /// Points added to the score of every team that didn't open the game, see [`Board::score`].
+    pub komi: i32,
+    /// Stones of [`Rules::handicap_team`] placed before the first move, so that a weaker player
+    /// can start with an advantage.
+    pub handicap: usize,
+    /// The team that receives the handicap stones, the second team by default.
+    pub handicap_team: Team,
+    pub win: WinCondition,
+    pub stones_per_turn: StonesPerTurn,
+    /// Whether the placements stop when a single cell is left empty, and from then on the teams
+    /// move their stones instead, either one step to a neighbour cell or to any cell that the
+    /// previous move vacated with its pulls. The moved stone pulls or pushes when it lands,
+    /// with the polarity of the rules, or attracting when the players choose it for each stone.
+    pub relocation: bool,
+}
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            reach: Reach::Unlimited,
+            magnetism: Magnetism::Orthogonal,
+            polarity: PolarityRule::Attract,
+            turn_order: TurnOrder::default(),
+            repetition: Repetition::Allowed,
+            pie: false,
+            komi: 0,
+            handicap: 0,
+            handicap_team: Team::Black,
+            win: WinCondition::Majority,
+            stones_per_turn: StonesPerTurn::One,
+            relocation: false,
         }
     }
 }
 
+/// The largest komi offered in the settings.
+pub const MAX_KOMI: i32 = 5;
+/// The largest handicap offered in the settings.
+pub const MAX_HANDICAP: usize = 9;
+
+/// Pairs of opposite directions in which a new stone pulls the first opponent stone.
+const ORTHOGONAL_AXES: [(Coord, Coord); 2] = [
+    (Coord::new(1, 0), Coord::new(-1, 0)),
+    (Coord::new(0, 1), Coord::new(0, -1)),
+];
+const DIAGONAL_AXES: [(Coord, Coord); 2] = [
+    (Coord::new(1, 1), Coord::new(-1, -1)),
+    (Coord::new(1, -1), Coord::new(-1, 1)),
+];
+const ALL_AXES: [(Coord, Coord); 4] = [
+    ORTHOGONAL_AXES[0],
+    ORTHOGONAL_AXES[1],
+    DIAGONAL_AXES[0],
+    DIAGONAL_AXES[1],
+];
+/// In a hexagonal board, the row below a cell is shifted half a cell to the right, so the
+/// neighbours of (x, y) in that row are (x - 1, y + 1) and (x, y + 1).
+const HEXAGONAL_AXES: [(Coord, Coord); 3] = [
+    ORTHOGONAL_AXES[0],
+    ORTHOGONAL_AXES[1],
+    (Coord::new(1, -1), Coord::new(-1, 1)),
+];
+
 pub type Board2d = Vec<Vec<Team>>;
-type History = Vec<Board2d>;
 
+/// The cells that a new stone looks at in one direction, walked one step at a time.
+struct Ray {
+    adjacent: Coord,
+    position: Coord,
+    direction: Coord,
+    keep_checking: bool,
+}
+
+#[derive(Clone)]
 pub struct Board {
     pub size_rows: i32,
     pub size_columns: i32,
+    pub topology: Topology,
+    pub rules: Rules,
     pub board: Board2d,
-    pub board_history: History,
+    /// Blocked cells, walls and starting stones. Change it with [`Board::set_layout`].
+    pub layout: Layout,
+    pub variations: Variations,
     pub turn: Team,
+    pub resigned: Option<Team>,
+    /// Hashes of the positions before each move of the current line, only tracked if the rules
+    /// care about repetitions.
+    seen: Vec<u64>,
+    /// Whether the last move recreated an earlier position.
+    repeated: bool,
+    /// How many turns in a row have been passed. The game ends when every team passes.
+    passes: usize,
+    /// How many moves have been played since the start of the game, including passes.
+    moves_played: usize,
+    /// The team that fulfilled the win condition with the last move, if any.
+    reached_goal: Option<Team>,
+    /// Whether the team in turn already placed a stone and has to place another one.
+    second_placement: bool,
+    /// The cells that the last move vacated with its pulls, where a relocated stone can land.
+    vacated: Vec<Coord>,
+    /// Whether the second team took the opening stone with [`Move::Swap`].
+    swapped: bool,
 }
 
 impl Board {
     pub fn new(size_rows: i32, size_columns: i32) -> Self {
         let board = new_board(size_rows, size_columns);
-        let board_history = Vec::new();
-        let turn = Team::White;
+        let turn = Rules::default().turn_order.first();
         Self {
             size_rows,
             size_columns,
+            topology: Topology::Torus,
+            rules: Rules::default(),
             board,
-            board_history,
+            layout: Layout::empty(size_rows, size_columns),
+            variations: Variations::new(),
             turn,
+            resigned: None,
+            seen: Vec::new(),
+            repeated: false,
+            passes: 0,
+            moves_played: 0,
+            reached_goal: None,
+            second_placement: false,
+            vacated: Vec::new(),
+            swapped: false,
         }
     }
     pub fn new_default_size() -> Self {
         Self::new(7, 5)
     }
     pub fn reset(&mut self) {
-        *self = Self::new(self.size_rows, self.size_columns);
+        self.resize(self.size_rows, self.size_columns);
+    }
+    /// Starts a new game with the given size. The layout is kept only if it has that size.
+    pub fn resize(&mut self, size_rows: i32, size_columns: i32) {
+        let topology = self.topology;
+        let rules = self.rules;
+        let layout = std::mem::replace(&mut self.layout, Layout::empty(0, 0));
+        *self = Self::new(size_rows, size_columns);
+        self.topology = topology;
+        self.rules = rules;
+        self.turn = rules.turn_order.first();
+        if layout.size() == self.size() {
+            self.layout = layout;
+        }
+        for (coord, team) in self.layout.stones() {
+            self.board[coord.x as usize][coord.y as usize] = team;
+        }
+        self.place_handicap();
+    }
+
+    /// Places the handicap stones, for the second team if the handicap team is not playing. Each
+    /// one goes to the free cell furthest from the stones already on the board, where the edges
+    /// that don't wrap count as stones just outside of it. Ties go to the cell closest to the
+    /// centre.
+    fn place_handicap(&mut self) {
+        let turn_order = self.rules.turn_order;
+        let team = if turn_order.teams().contains(&self.rules.handicap_team) {
+            self.rules.handicap_team
+        } else {
+            turn_order.after(turn_order.first())
+        };
+        for _ in 0..self.rules.handicap {
+            let stones = self
+                .cells()
+                .filter(|coord| self.get_team(*coord) != Team::Empty)
+                .collect::<Vec<_>>();
+            let best = self
+                .cells()
+                .filter(|coord| self.is_free(*coord))
+                .max_by_key(|coord| (self.spread(*coord, &stones), -self.centre_distance(*coord)));
+            match best {
+                Some(coord) => *self.get_team_mut(coord) = team,
+                None => break,
+            }
+        }
+    }
+
+    /// The squared distance from the cell to the closest of the stones, or to the closest edge
+    /// that doesn't wrap.
+    fn spread(&self, coord: Coord, stones: &[Coord]) -> i32 {
+        let mut closest = i32::MAX;
+        if !self.topology.wraps_x() {
+            let edge = (coord.x + 1).min(self.size_columns - coord.x);
+            closest = closest.min(edge * edge);
+        }
+        if !self.topology.wraps_y() {
+            let edge = (coord.y + 1).min(self.size_rows - coord.y);
+            closest = closest.min(edge * edge);
+        }
+        for stone in stones {
+            let mut dx = (coord.x - stone.x).abs();
+            let mut dy = (coord.y - stone.y).abs();
+            if self.topology.wraps_x() {
+                dx = dx.min(self.size_columns - dx);
+            }
+            if self.topology.wraps_y() {
+                dy = dy.min(self.size_rows - dy);
+            }
+            closest = closest.min(dx * dx + dy * dy);
+        }
+        closest
+    }
+
+    /// Four times the squared distance from the centre of the cell to the centre of the board.
+    fn centre_distance(&self, coord: Coord) -> i32 {
+        let dx = 2 * coord.x + 1 - self.size_columns;
+        let dy = 2 * coord.y + 1 - self.size_rows;
+        dx * dx + dy * dy
+    }
+    /// Starts a new game with the given topology.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        self.reset();
+    }
+    /// Starts a new game with the given rules.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+        self.reset();
+    }
+    /// Changes the map and starts a new game on it.
+    pub fn edit_layout(&mut self, edit: impl FnOnce(&mut Layout)) {
+        edit(&mut self.layout);
+        self.reset();
+    }
+    /// Starts a new game on the given map, resizing the board to it.
+    pub fn set_layout(&mut self, layout: Layout) {
+        let (size_rows, size_columns) = layout.size();
+        self.layout = layout;
+        self.resize(size_rows, size_columns);
     }
 
     pub fn size(&self) -> (i32, i32) {
         (self.size_rows, self.size_columns)
     }
-    pub fn maybe_change_size(&mut self, buttons: &mut Buttons) {
-        maybe_change_size(
-            &mut self.size_rows,
-            &mut self.size_columns,
-            buttons,
-            &mut self.board,
-        )
-    }
     pub fn pop_history(&mut self) {
-        let previous = self.board_history.pop();
-        if let Some(b) = previous {
-            self.board = b;
-            self.turn = self.turn.toggle()
+        if self.resigned.take().is_some() {
+            return;
+        }
+        if let Some(outcome) = self.variations.undo() {
+            self.revert(&outcome);
+        };
+    }
+    /// Plays again the last move taken back, if any.
+    pub fn redo(&mut self) -> Option<MoveOutcome> {
+        let mv = self.variations.next_move()?;
+        self.play(mv)
+    }
+    /// Takes back the last move and forgets it, along with all its continuations.
+    pub fn delete_variation(&mut self) {
+        self.resigned = None;
+        if let Some(outcome) = self.variations.delete() {
+            self.revert(&outcome);
+        };
+    }
+    /// The moves played since the start of the game.
+    pub fn moves(&self) -> Vec<Move> {
+        self.variations.moves()
+    }
+    /// Whether the team in turn already placed a stone in this turn and has to place another.
+    pub fn is_second_placement(&self) -> bool {
+        self.second_placement
+    }
+    /// The stones of the team, plus the komi unless the team opened the game. After a swap, the
+    /// opening stone belongs to the second team, so the komi goes to the first one.
+    pub fn score(&self, team: Team) -> i32 {
+        let first = self.rules.turn_order.first();
+        let opener = if self.swapped {
+            self.rules.turn_order.after(first)
+        } else {
+            first
+        };
+        let komi = if team == opener { 0 } else { self.rules.komi };
+        compute_score(&self.board, team) + komi
+    }
+    /// The score of each playing team, in turn order.
+    pub fn scores(&self) -> Vec<(Team, i32)> {
+        let teams = self.rules.turn_order.teams().iter();
+        teams.map(|team| (*team, self.score(*team))).collect()
+    }
+
+    pub fn resign(&mut self, team: Team) {
+        if self.resigned.is_none() {
+            self.resigned = Some(team);
+        }
+    }
+    /// Returns None while the game is still going on.
+    pub fn result(&self) -> Option<GameResult> {
+        if let Some(team) = self.resigned {
+            Some(GameResult::Resignation(team))
+        } else if let Some(team) = self.reached_goal {
+            Some(GameResult::Win(team))
+        } else if self.repeated {
+            Some(GameResult::Draw)
+        } else if self.everyone_passed() || !self.anyone_can_play_stone() {
+            let scores = self.scores();
+            let best = scores.iter().map(|(_team, score)| *score).max().unwrap();
+            let mut leaders = scores.iter().filter(|(_team, score)| *score == best);
+            Some(match (leaders.next(), leaders.next()) {
+                (Some((team, _score)), None) => GameResult::Win(*team),
+                _ => GameResult::Draw,
+            })
+        } else {
+            None
+        }
+    }
+    /// The team that won, if the game is over and it was not a draw. With more than two teams,
+    /// a resignation ends the game without a winner.
+    pub fn winner(&self) -> Option<Team> {
+        match self.result()? {
+            GameResult::Win(team) => Some(team),
+            GameResult::Draw => None,
+            GameResult::Resignation(team) => {
+                let teams = self.rules.turn_order.teams();
+                if teams.len() == 2 {
+                    teams.iter().copied().find(|t| *t != team)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+    pub fn is_terminal(&self) -> bool {
+        self.result().is_some()
+    }
+
+    pub fn contains(&self, coord: Coord) -> bool {
+        0 <= coord.x && coord.x < self.size_columns && 0 <= coord.y && coord.y < self.size_rows
+    }
+    /// Moves one step from `coord`, crossing the edges according to the topology. Returns the
+    /// new cell and the direction to keep walking in, which changes when the crossed edge is
+    /// mirrored, or None if the step would fall off the board, cross a wall or enter a blocked
+    /// cell.
+    pub fn step(&self, coord: Coord, direction: Coord) -> Option<(Coord, Coord)> {
+        let (next, direction) = self.cross_edges(coord, direction)?;
+        if self.layout.is_blocked(next) || self.layout.has_wall(coord, next) {
+            return None;
+        }
+        Some((next, direction))
+    }
+    /// The neighbour of `coord` in that direction, ignoring walls and blocked cells.
+    pub fn neighbour(&self, coord: Coord, direction: Coord) -> Option<Coord> {
+        self.cross_edges(coord, direction)
+            .map(|(next, _direction)| next)
+    }
+    /// The directions to the cells that share an edge with each cell, where walls can be built.
+    pub fn edge_directions(&self) -> Vec<Coord> {
+        let axes: &[(Coord, Coord)] = if self.topology.is_hexagonal() {
+            &HEXAGONAL_AXES
+        } else {
+            &ORTHOGONAL_AXES
+        };
+        axes.iter()
+            .flat_map(|(positive, negative)| [*positive, *negative])
+            .collect()
+    }
+    fn cross_edges(&self, coord: Coord, direction: Coord) -> Option<(Coord, Coord)> {
+        let mut next = coord + direction;
+        let mut direction = direction;
+        if next.x < 0 || next.x >= self.size_columns {
+            if !self.topology.wraps_x() {
+                return None;
+            }
+            next.x = next.x.rem_euclid(self.size_columns);
+            if self.topology.mirrors_when_wrapping_x() {
+                next.y = self.size_rows - 1 - next.y;
+                direction.y = -direction.y;
+            }
+        }
+        if next.y < 0 || next.y >= self.size_rows {
+            if !self.topology.wraps_y() {
+                return None;
+            }
+            next.y = next.y.rem_euclid(self.size_rows);
+            if self.topology.mirrors_when_wrapping_y() {
+                next.x = self.size_columns - 1 - next.x;
+                direction.x = -direction.x;
+            }
+        }
+        Some((next, direction))
+    }
+    /// The coordinate must be inside the board.
+    pub fn get_team(&self, coord: Coord) -> Team {
+        self.board[coord.x as usize][coord.y as usize]
+    }
+    fn get_team_mut(&mut self, coord: Coord) -> &mut Team {
+        &mut self.board[coord.x as usize][coord.y as usize]
+    }
+
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.is_legal_when(mv, self.is_relocating())
+    }
+    /// Like [`Board::is_legal`], with [`Board::is_relocating`] already computed, as it takes a
+    /// pass over the board.
+    fn is_legal_when(&self, mv: Move, relocating: bool) -> bool {
+        self.is_playable(mv, relocating)
+            && !(self.rules.repetition == Repetition::Superko && self.repeats(mv))
+    }
+    /// Whether the move could be played, without checking if it repeats a position.
+    fn is_playable(&self, mv: Move, relocating: bool) -> bool {
+        let over = self.resigned.is_some() || self.reached_goal.is_some() || self.repeated;
+        if over || self.everyone_passed() {
+            return false;
+        }
+        let choosing_polarity = self.rules.polarity == PolarityRule::PlayerChoice;
+        match mv {
+            Move::Place(coord) if !choosing_polarity => !relocating && self.is_free(coord),
+            Move::PlacePolarized(coord, _) if choosing_polarity => {
+                !relocating && self.is_free(coord)
+            }
+            Move::Relocate { from, to } => relocating && self.can_relocate(from, to),
+            // a team that can't move passes, while others still can
+            Move::Pass => !self.second_placement && self.anyone_can_play_stone(),
+            Move::Swap => {
+                self.rules.pie
+                    && !self.is_full()
+                    && self.rules.turn_order.teams().len() == 2
+                    && self.moves_played == 1
+                    && self.passes == 0
+            }
+            _ => false,
+        }
+    }
+    /// Whether a stone can be placed in the cell.
+    fn is_free(&self, coord: Coord) -> bool {
+        self.contains(coord)
+            && self.get_team(coord) == Team::Empty
+            && !self.layout.is_blocked(coord)
+    }
+    /// Whether there is no cell left to place a stone.
+    fn is_full(&self) -> bool {
+        !self.cells().any(|coord| self.is_free(coord))
+    }
+    /// Whether the placements are over and the teams move their stones instead. This starts
+    /// with a single free cell left instead of none, because on a full board no stone could
+    /// move nor pull.
+    pub fn is_relocating(&self) -> bool {
+        self.rules.relocation
+            && self
+                .cells()
+                .filter(|coord| self.is_free(*coord))
+                .nth(1)
+                .is_none()
+    }
+    /// Whether the team in turn can move the stone, assuming that the teams are relocating.
+    fn can_relocate(&self, from: Coord, to: Coord) -> bool {
+        self.contains(from)
+            && self.get_team(from) == self.turn
+            && self.is_free(to)
+            && self
+                .relocation_targets(from, &self.edge_directions())
+                .contains(&to)
+    }
+    /// The cells where the stone could move if they were free: the ones vacated by the last
+    /// move and its neighbours.
+    fn relocation_targets(&self, from: Coord, directions: &[Coord]) -> Vec<Coord> {
+        let mut targets = self.vacated.clone();
+        for direction in directions {
+            if let Some((next, _direction)) = self.step(from, *direction) {
+                if !targets.contains(&next) {
+                    targets.push(next);
+                }
+            }
+        }
+        targets
+    }
+    /// The stone moves of the team, assuming that the teams are relocating, without checking if
+    /// they repeat a position.
+    fn relocations(&self, team: Team) -> Vec<Move> {
+        let directions = self.edge_directions();
+        let mut moves = Vec::new();
+        for from in self.cells().filter(|coord| self.get_team(*coord) == team) {
+            for to in self.relocation_targets(from, &directions) {
+                if self.is_free(to) {
+                    moves.push(Move::Relocate { from, to });
+                }
+            }
+        }
+        moves
+    }
+    /// Whether any team can place or move a stone. The game ends when none can.
+    fn anyone_can_play_stone(&self) -> bool {
+        if self.is_relocating() {
+            let teams = self.rules.turn_order.teams();
+            teams.iter().any(|team| !self.relocations(*team).is_empty())
+        } else {
+            !self.is_full()
+        }
+    }
+    fn cells(&self) -> impl Iterator<Item = Coord> {
+        let size_rows = self.size_rows;
+        (0..self.size_columns).flat_map(move |x| (0..size_rows).map(move |y| Coord::new(x, y)))
+    }
+    fn everyone_passed(&self) -> bool {
+        self.passes >= self.rules.turn_order.teams().len()
+    }
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let relocating = self.is_relocating();
+        let mut moves = Vec::new();
+        for x in 0..self.size_columns {
+            for y in 0..self.size_rows {
+                let coord = Coord::new(x, y);
+                let candidates = if self.rules.polarity == PolarityRule::PlayerChoice {
+                    vec![
+                        Move::PlacePolarized(coord, Polarity::Attract),
+                        Move::PlacePolarized(coord, Polarity::Repel),
+                    ]
+                } else {
+                    vec![Move::Place(coord)]
+                };
+                for mv in candidates {
+                    if self.is_legal_when(mv, relocating) {
+                        moves.push(mv);
+                    }
+                }
+            }
+        }
+        if relocating {
+            for mv in self.relocations(self.turn) {
+                if self.is_legal_when(mv, relocating) {
+                    moves.push(mv);
+                }
+            }
+        }
+        for mv in [Move::Pass, Move::Swap] {
+            if self.is_legal_when(mv, relocating) {
+                moves.push(mv);
+            }
+        }
+        moves
+    }
+    /// The move that places a stone on the coordinate, with the given polarity if the rules let
+    /// the player choose it.
+    pub fn place_move(&self, coord: Coord, polarity: Polarity) -> Move {
+        if self.rules.polarity == PolarityRule::PlayerChoice {
+            Move::PlacePolarized(coord, polarity)
+        } else {
+            Move::Place(coord)
+        }
+    }
+
+    /// Applies the move for the team in turn and passes the turn, without touching the history.
+    /// Returns None if the move is not legal.
+    pub fn apply(&mut self, mv: Move) -> Option<MoveOutcome> {
+        if !self.is_legal(mv) {
+            return None;
+        }
+        Some(self.apply_playable(mv))
+    }
+
+    /// Whether playing the move would recreate an earlier position.
+    fn repeats(&self, mv: Move) -> bool {
+        let mut copy = Board {
+            rules: Rules {
+                repetition: Repetition::Draw,
+                ..self.rules
+            },
+            ..self.preview_copy()
         };
+        copy.apply_playable(mv);
+        copy.repeated
+    }
+
+    /// The hash of the stones and the turn, see [`crate::zobrist`].
+    pub fn hash(&self) -> u64 {
+        zobrist::hash(&self.board, self.turn)
+    }
+
+    fn tracks_repetitions(&self) -> bool {
+        self.rules.repetition != Repetition::Allowed
     }
-    pub fn score(&self) -> (i32, i32) {
-        compute_score(&self.board)
+
+    /// Like [`Board::apply`], for moves that are known to be playable.
+    fn apply_playable(&mut self, mv: Move) -> MoveOutcome {
+        if self.tracks_repetitions() {
+            self.seen.push(self.hash());
+        }
+        let team = self.turn;
+        let previous_passes = self.passes;
+        let placed = mv.coord();
+        let pulls = match mv {
+            Move::Place(coord) if self.rules.polarity == PolarityRule::Repel => {
+                self.place(team, coord, Polarity::Repel)
+            }
+            Move::Place(coord) => self.place(team, coord, Polarity::Attract),
+            Move::PlacePolarized(coord, polarity) => self.place(team, coord, polarity),
+            Move::Pass => Vec::new(),
+            Move::Swap => {
+                self.swap_colours();
+                self.swapped = true;
+                Vec::new()
+            }
+            Move::Relocate { from, to } => {
+                *self.get_team_mut(from) = Team::Empty;
+                let polarity = match self.rules.polarity {
+                    PolarityRule::Repel => Polarity::Repel,
+                    PolarityRule::Attract | PolarityRule::PlayerChoice => Polarity::Attract,
+                };
+                self.place(team, to, polarity)
+            }
+        };
+        let lifted = match mv {
+            Move::Relocate { from, .. } => Some(from),
+            _ => None,
+        };
+        let vacated = pulls.iter().map(|pull| pull.from).collect();
+        let previous_vacated = std::mem::replace(&mut self.vacated, vacated);
+        self.passes = if mv == Move::Pass {
+            previous_passes + 1
+        } else {
+            0
+        };
+        let second_placement = self.second_placement;
+        // the team places another stone unless this was its last one or the first of the game
+        self.second_placement = placed.is_some()
+            && !second_placement
+            && self.rules.stones_per_turn == StonesPerTurn::Two
+            && self.moves_played > 0;
+        self.moves_played += 1;
+        if !self.second_placement {
+            self.turn = self.rules.turn_order.after(team);
+        }
+        // when every team passes the position repeats, but that ends the game by score instead
+        if self.tracks_repetitions() && mv != Move::Pass {
+            self.repeated = self.seen.contains(&self.hash());
+        }
+        if mv != Move::Pass {
+            self.reached_goal = self.first_to_reach_goal(team);
+        }
+        MoveOutcome {
+            team,
+            placed,
+            pulls,
+            previous_passes,
+            swapped: mv == Move::Swap,
+            second_placement,
+            lifted,
+            previous_vacated,
+        }
     }
+
+    /// Gives the stones of the first team to the second one and vice versa. After the first
+    /// move, this leaves the position as if the second player had opened instead.
+    fn swap_colours(&mut self) {
+        let first = self.rules.turn_order.first();
+        let second = self.rules.turn_order.after(first);
+        for cell in self.board.iter_mut().flatten() {
+            if *cell == first {
+                *cell = second;
+            } else if *cell == second {
+                *cell = first;
+            }
+        }
+    }
+
+    /// Puts a stone of the team in the cell and moves the stones that it pulls or pushes.
+    fn place(&mut self, team: Team, placed: Coord, polarity: Polarity) -> Vec<Pull> {
+        *self.get_team_mut(placed) = team;
+        let mut pulls = Vec::new();
+        for &(positive, negative) in self.axes() {
+            let axis_length = self.axis_length(positive);
+            let max_distance = self.rules.reach.max_distance(axis_length);
+            match polarity {
+                Polarity::Attract => {
+                    let mut rays = [
+                        self.start_ray(placed, positive),
+                        self.start_ray(placed, negative),
+                    ];
+                    for _distance in 2..=max_distance {
+                        for ray in &mut rays {
+                            pulls.extend(self.check_direction(team, ray));
+                        }
+                    }
+                }
+                Polarity::Repel => {
+                    for direction in [positive, negative] {
+                        pulls.extend(self.repel(team, placed, direction, max_distance));
+                    }
+                }
+            }
+        }
+        pulls
+    }
+
+    /// What the move would do, without changing the board.
+    pub fn preview(&self, mv: Move) -> Option<MoveOutcome> {
+        self.preview_copy().apply(mv)
+    }
+
+    /// A copy of the position without the variations, which are not needed to try moves.
+    fn preview_copy(&self) -> Board {
+        Board {
+            board: self.board.clone(),
+            layout: self.layout.clone(),
+            variations: Variations::new(),
+            seen: self.seen.clone(),
+            vacated: self.vacated.clone(),
+            ..*self
+        }
+    }
+
+    /// Applies the move and stores the previous position so that it can be undone. If the move
+    /// was already tried from this position, its line is followed instead of creating a new one.
+    pub fn play(&mut self, mv: Move) -> Option<MoveOutcome> {
+        let outcome = self.apply(mv)?;
+        self.variations.push(mv, outcome.clone());
+        Some(outcome)
+    }
+
+    /// Undoes exactly what [`Board::apply`] did, given the outcome it returned.
+    pub fn revert(&mut self, outcome: &MoveOutcome) {
+        for pull in outcome.pulls.iter().rev() {
+            *self.get_team_mut(pull.to) = Team::Empty;
+            *self.get_team_mut(pull.from) = pull.team;
+        }
+        if let Some(placed) = outcome.placed {
+            *self.get_team_mut(placed) = Team::Empty;
+        }
+        if let Some(lifted) = outcome.lifted {
+            *self.get_team_mut(lifted) = outcome.team;
+        }
+        self.vacated = outcome.previous_vacated.clone();
+        if outcome.swapped {
+            self.swap_colours();
+            self.swapped = false;
+        }
+        self.turn = outcome.team;
+        self.second_placement = outcome.second_placement;
+        self.passes = outcome.previous_passes;
+        self.moves_played -= 1;
+        // reaching the goal ends the game, so nobody had reached it before
+        self.reached_goal = None;
+        if self.tracks_repetitions() {
+            // a repeated position ends the game, so the previous one was not repeated
+            self.seen.pop();
+            self.repeated = false;
+        }
+    }
+
+    /// The team that fulfills the win condition, checking first the team that just moved, as
+    /// its move could also complete the goal of a team whose stones it pulled.
+    fn first_to_reach_goal(&self, mover: Team) -> Option<Team> {
+        let mut team = mover;
+        for _ in self.rules.turn_order.teams() {
+            let reached = match self.rules.win {
+                WinCondition::Majority => false,
+                WinCondition::Stones(stones) => compute_score(&self.board, team) >= stones,
+                WinCondition::Line(length) => self.has_line(team, length),
+            };
+            if reached {
+                return Some(team);
+            }
+            team = self.rules.turn_order.after(team);
+        }
+        None
+    }
+
+    /// Whether the team has `length` stones in a row along an edge direction. The line follows
+    /// the wrapping edges, but not across walls, and it can't use the same cell twice.
+    fn has_line(&self, team: Team, length: i32) -> bool {
+        for start in self.cells().filter(|coord| self.get_team(*coord) == team) {
+            for direction in self.edge_directions() {
+                let mut line = vec![start];
+                let (mut coord, mut direction) = (start, direction);
+                while (line.len() as i32) < length {
+                    match self.step(coord, direction) {
+                        Some((next, next_direction))
+                            if self.get_team(next) == team && !line.contains(&next) =>
+                        {
+                            line.push(next);
+                            (coord, direction) = (next, next_direction);
+                        }
+                        _ => break,
+                    }
+                }
+                if line.len() as i32 >= length {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn axes(&self) -> &'static [(Coord, Coord)] {
+        if self.topology.is_hexagonal() {
+            &HEXAGONAL_AXES
+        } else {
+            self.rules.magnetism.axes()
+        }
+    }
+
+    /// How many steps it takes to go around the board in this direction, on a torus.
+    fn axis_length(&self, direction: Coord) -> i32 {
+        match (direction.x != 0, direction.y != 0) {
+            (true, false) => self.size_columns,
+            (false, true) => self.size_rows,
+            _ => lcm(self.size_columns, self.size_rows),
+        }
+    }
+
+    fn start_ray(&self, new_stone: Coord, direction: Coord) -> Ray {
+        if let Some((adjacent, direction)) = self.step(new_stone, direction) {
+            Ray {
+                adjacent,
+                position: adjacent,
+                direction,
+                keep_checking: self.get_team(adjacent) == Team::Empty,
+            }
+        } else {
+            Ray {
+                adjacent: new_stone,
+                position: new_stone,
+                direction,
+                keep_checking: false,
+            }
+        }
+    }
+
+    /// Pushes the nearest stone within `max_distance`, if it's an opponent's, away from the new
+    /// stone until it hits another stone or the edge of the board.
+    fn repel(
+        &mut self,
+        new_stone_color: Team,
+        new_stone: Coord,
+        mut direction: Coord,
+        max_distance: i32,
+    ) -> Option<Pull> {
+        let mut position = new_stone;
+        for _distance in 1..=max_distance {
+            (position, direction) = self.step(position, direction)?;
+            let color = self.get_team(position);
+            if color == new_stone_color {
+                return None;
+            } else if color != Team::Empty {
+                let from = position;
+                while let Some((next, next_direction)) = self.step(position, direction) {
+                    if self.get_team(next) != Team::Empty {
+                        break;
+                    }
+                    position = next;
+                    direction = next_direction;
+                }
+                if position == from {
+                    return None;
+                }
+                *self.get_team_mut(from) = Team::Empty;
+                *self.get_team_mut(position) = new_stone_color;
+                return Some(Pull {
+                    from,
+                    to: position,
+                    team: color,
+                });
+            }
+        }
+        None
+    }
+
+    fn check_direction(&mut self, new_stone_color: Team, ray: &mut Ray) -> Option<Pull> {
+        if ray.keep_checking {
+            if let Some((pulled_stone, direction)) = self.step(ray.position, ray.direction) {
+                ray.position = pulled_stone;
+                ray.direction = direction;
+                let pulled_stone_color = self.get_team(pulled_stone);
+                if new_stone_color == pulled_stone_color {
+                    ray.keep_checking = false;
+                } else if pulled_stone_color != Team::Empty {
+                    *self.get_team_mut(ray.adjacent) = new_stone_color;
+                    *self.get_team_mut(pulled_stone) = Team::Empty;
+                    ray.keep_checking = false;
+                    return Some(Pull {
+                        from: pulled_stone,
+                        to: ray.adjacent,
+                        team: pulled_stone_color,
+                    });
+                }
+            } else {
+                ray.keep_checking = false;
+            }
+        }
+        None
+    }
+}
+
+fn lcm(a: i32, b: i32) -> i32 {
+    let gcd = |mut a: i32, mut b: i32| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
 }
 
 fn new_board(rows: i32, columns: i32) -> Vec<Vec<Team>> {
@@ -89,52 +1376,648 @@ fn new_board(rows: i32, columns: i32) -> Vec<Vec<Team>> {
     board
 }
 
-fn maybe_change_size(
-    size_rows: &mut i32,
-    size_columns: &mut i32,
-    buttons: &mut game::Buttons, // TODO: extract to Actions to extract side-effects?
-    board: &mut Vec<Vec<Team>>,
-) {
-    let mut changed_rows = false;
-    let mut changed_columns = false;
-    if buttons.rows.increase.interact().is_clicked() {
-        *size_rows += 1;
-        changed_rows = true;
+fn compute_score(board: &[Vec<Team>], team: Team) -> i32 {
+    let mut stones = 0;
+    for column in board {
+        stones += column.iter().filter(|cell| **cell == team).count() as i32;
     }
-    if buttons.rows.decrease.interact().is_clicked() {
-        *size_rows -= 1;
-        changed_rows = true;
+    stones
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_stone_basic() {
+        let mut board = Board::new(5, 5);
+        board.board[1][2] = Team::Black;
+        let outcome = board.apply(place(3, 2)).unwrap();
+        assert_eq!(board.turn, Team::Black);
+        assert_eq!(board.board[1][2], Team::Empty);
+        assert_eq!(board.board[2][2], Team::White);
+        assert_eq!(board.board[3][2], Team::White);
+        assert_eq!(
+            outcome.pulls,
+            vec![Pull {
+                from: Coord::new(1, 2),
+                to: Coord::new(2, 2),
+                team: Team::Black,
+            }]
+        );
     }
-    if buttons.columns.increase.interact().is_clicked() {
-        *size_columns += 1;
-        changed_columns = true;
+
+    #[test]
+    fn test_put_stone_toroid() {
+        let mut board = Board::new(5, 5);
+        board.board[1][2] = Team::Black;
+        board.apply(place(4, 2)).unwrap();
+        assert_eq!(board.turn, Team::Black);
+        assert_eq!(board.board[1][2], Team::Empty);
+        assert_eq!(board.board[0][2], Team::White);
+        assert_eq!(board.board[4][2], Team::White);
     }
-    if buttons.columns.decrease.interact().is_clicked() {
-        *size_columns -= 1;
-        changed_columns = true;
+
+    #[test]
+    fn test_put_stone_plane() {
+        let mut board = Board::new(5, 5);
+        board.topology = Topology::Plane;
+        board.board[1][2] = Team::Black;
+        board.board[2][2] = Team::White;
+        let outcome = board.apply(place(4, 2)).unwrap();
+        assert_eq!(outcome.pulls, vec![]);
+        assert_eq!(board.board[1][2], Team::Black);
+        assert_eq!(board.board[0][2], Team::Empty);
     }
-    if changed_rows {
-        buttons.rows.update(*size_rows);
+
+    #[test]
+    fn test_put_stone_klein_bottle() {
+        let mut board = Board::new(5, 5);
+        board.topology = Topology::KleinBottle;
+        board.board[1][3] = Team::Black;
+        let outcome = board.apply(place(4, 1)).unwrap();
+        assert_eq!(
+            outcome.pulls,
+            vec![Pull {
+                from: Coord::new(1, 3),
+                to: Coord::new(0, 3),
+                team: Team::Black,
+            }]
+        );
+        assert_eq!(board.board[0][3], Team::White);
     }
-    if changed_columns {
-        buttons.columns.update(*size_columns);
+
+    #[test]
+    fn test_step_projective_plane() {
+        let mut board = Board::new(4, 5);
+        board.topology = Topology::ProjectivePlane;
+        let down = Coord::new(0, 1);
+        assert_eq!(
+            board.step(Coord::new(1, 3), down),
+            Some((Coord::new(3, 0), down))
+        );
+        let diagonal = Coord::new(1, 1);
+        assert_eq!(
+            board.step(Coord::new(4, 1), diagonal),
+            Some((Coord::new(0, 1), Coord::new(1, -1)))
+        );
     }
-    if changed_rows || changed_columns {
-        *board = new_board(*size_rows, *size_columns);
+
+    #[test]
+    fn test_can_not_put_stone() {
+        let mut board = Board::new(5, 5);
+        board.board[1][2] = Team::Black;
+        assert!(!board.is_legal(place(1, 2)));
+        assert_eq!(board.apply(place(1, 2)), None);
+        assert_eq!(board.turn, Team::White);
+        assert_eq!(board.board[1][2], Team::Black);
     }
-}
 
-fn compute_score(board: &Vec<Vec<Team>>) -> (i32, i32) {
-    let mut whites = 0;
-    let mut blacks = 0;
-    for column in board {
-        for team in column {
-            match team {
-                Team::Empty => {}
-                Team::White => whites += 1,
-                Team::Black => blacks += 1,
+    #[test]
+    fn test_legal_moves() {
+        let mut board = Board::new(2, 3);
+        assert_eq!(board.legal_moves().len(), 7);
+        assert!(board.legal_moves().contains(&Move::Pass));
+        assert!(!board.is_legal(place(3, 0)));
+        board.apply(place(1, 1)).unwrap();
+        assert_eq!(board.legal_moves().len(), 6);
+        assert!(!board.legal_moves().contains(&place(1, 1)));
+    }
+
+    #[test]
+    fn test_result_full_board() {
+        let mut board = Board::new(1, 3);
+        board.topology = Topology::Plane;
+        board.play(place(0, 0)).unwrap();
+        board.play(place(1, 0)).unwrap();
+        assert_eq!(board.result(), None);
+        board.play(place(2, 0)).unwrap();
+        assert!(board.is_terminal());
+        assert_eq!(board.result(), Some(GameResult::Win(Team::White)));
+    }
+
+    #[test]
+    fn test_resignation() {
+        let mut board = Board::new(5, 5);
+        board.play(place(0, 0)).unwrap();
+        board.resign(Team::Black);
+        assert_eq!(board.result(), Some(GameResult::Resignation(Team::Black)));
+        assert_eq!(board.winner(), Some(Team::White));
+        assert!(board.legal_moves().is_empty());
+        board.pop_history();
+        assert_eq!(board.result(), None);
+        assert_eq!(board.board[0][0], Team::White);
+    }
+
+    #[test]
+    fn test_pass() {
+        let mut board = Board::new(5, 5);
+        board.play(place(0, 0)).unwrap();
+        board.play(Move::Pass).unwrap();
+        board.play(place(2, 2)).unwrap();
+        board.play(Move::Pass).unwrap();
+        assert_eq!(board.result(), None);
+        assert_eq!(board.turn, Team::White);
+        board.play(Move::Pass).unwrap();
+        assert_eq!(board.result(), Some(GameResult::Win(Team::White)));
+        assert!(board.legal_moves().is_empty());
+        assert_eq!(board.play(Move::Pass), None);
+
+        board.pop_history();
+        assert_eq!(board.result(), None);
+        assert_eq!(board.turn, Team::White);
+        board.play(place(4, 4)).unwrap();
+        board.play(Move::Pass).unwrap();
+        assert_eq!(board.result(), None);
+    }
+
+    #[test]
+    fn test_pie_rule() {
+        let mut board = Board::new(5, 5);
+        board.play(place(0, 0)).unwrap();
+        assert!(!board.is_legal(Move::Swap));
+        board.pop_history();
+
+        board.rules.pie = true;
+        board.rules.komi = 2;
+        assert!(!board.is_legal(Move::Swap));
+        board.play(place(0, 0)).unwrap();
+        assert!(board.legal_moves().contains(&Move::Swap));
+        assert_eq!(board.scores(), vec![(Team::White, 1), (Team::Black, 2)]);
+        board.play(Move::Swap).unwrap();
+        assert_eq!(board.board[0][0], Team::Black);
+        assert_eq!(board.turn, Team::White);
+        assert!(!board.is_legal(Move::Swap));
+        // the komi goes to the team that didn't open, which is now the first one
+        assert_eq!(board.scores(), vec![(Team::White, 2), (Team::Black, 1)]);
+        board.pop_history();
+        assert_eq!(board.board[0][0], Team::White);
+        assert_eq!(board.turn, Team::Black);
+        assert_eq!(board.scores(), vec![(Team::White, 1), (Team::Black, 2)]);
+    }
+
+    #[test]
+    fn test_handicap() {
+        let mut board = Board::new(9, 9);
+        board.topology = Topology::Plane;
+        board.set_rules(Rules {
+            handicap: 5,
+            ..board.rules
+        });
+        let black = |board: &Board| {
+            let cells = board
+                .cells()
+                .filter(|coord| board.get_team(*coord) == Team::Black);
+            cells.map(|coord| (coord.x, coord.y)).collect::<Vec<_>>()
+        };
+        assert_eq!(black(&board), vec![(2, 2), (2, 6), (4, 4), (6, 2), (6, 6)]);
+        assert_eq!(board.turn, Team::White);
+
+        board.set_topology(Topology::Torus);
+        board.set_rules(Rules {
+            handicap: 2,
+            ..board.rules
+        });
+        board.resize(8, 8);
+        assert_eq!(black(&board), vec![(0, 0), (4, 4)]);
+
+        board.set_rules(Rules {
+            handicap_team: Team::White,
+            ..board.rules
+        });
+        assert!(black(&board).is_empty());
+        assert_eq!(board.score(Team::White), 2);
+        assert_eq!(board.turn, Team::White);
+    }
+
+    #[test]
+    fn test_win_condition_names() {
+        let mut condition = WinCondition::Majority;
+        for _ in 0..7 {
+            assert_eq!(WinCondition::from_name(&condition.name()), Some(condition));
+            condition = condition.next();
+        }
+        assert_eq!(condition, WinCondition::Majority);
+        assert_eq!(WinCondition::from_name("line of 0"), None);
+    }
+
+    #[test]
+    fn test_first_to_stones() {
+        let mut board = Board::new(5, 5);
+        board.rules.win = WinCondition::Stones(2);
+        board.play(place(0, 0)).unwrap();
+        board.play(place(2, 2)).unwrap();
+        assert_eq!(board.result(), None);
+        board.play(place(4, 4)).unwrap();
+        assert_eq!(board.result(), Some(GameResult::Win(Team::White)));
+        assert!(board.legal_moves().is_empty());
+        board.pop_history();
+        assert_eq!(board.result(), None);
+    }
+
+    #[test]
+    fn test_line_wraps() {
+        for (topology, expected) in [
+            (Topology::Plane, None),
+            (Topology::Torus, Some(GameResult::Win(Team::White))),
+        ] {
+            let mut board = Board::new(5, 5);
+            board.topology = topology;
+            board.rules.win = WinCondition::Line(3);
+            board.board[3][0] = Team::White;
+            board.board[4][0] = Team::White;
+            board.apply(place(0, 0)).unwrap();
+            assert_eq!(board.result(), expected);
+        }
+    }
+
+    #[test]
+    fn test_two_stones_per_turn() {
+        let mut board = Board::new(5, 5);
+        board.rules.stones_per_turn = StonesPerTurn::Two;
+        board.play(place(0, 0)).unwrap();
+        assert_eq!(board.turn, Team::Black);
+        board.play(place(2, 2)).unwrap();
+        assert_eq!(board.turn, Team::Black);
+        assert!(board.is_second_placement());
+        assert!(!board.is_legal(Move::Pass));
+        board.play(place(4, 4)).unwrap();
+        assert_eq!(board.turn, Team::White);
+        assert!(!board.is_second_placement());
+
+        board.pop_history();
+        assert_eq!(board.turn, Team::Black);
+        assert!(board.is_second_placement());
+        board.pop_history();
+        assert_eq!(board.turn, Team::Black);
+        assert!(!board.is_second_placement());
+        board.pop_history();
+        assert_eq!(board.turn, Team::White);
+    }
+
+    #[test]
+    fn test_relocation() {
+        let mut board = Board::new(1, 5);
+        board.topology = Topology::Plane;
+        board.rules.relocation = true;
+        let row = |board: &Board| {
+            board
+                .board
+                .iter()
+                .map(|column| column[0])
+                .collect::<Vec<_>>()
+        };
+        let (w, b, e) = (Team::White, Team::Black, Team::Empty);
+        for (x, team) in [(0, b), (1, w), (3, w), (4, b)] {
+            board.board[x][0] = team;
+        }
+        assert!(board.is_relocating());
+        assert!(!board.is_legal(place(2, 0)));
+        let relocate = |from, to| Move::Relocate {
+            from: Coord::new(from, 0),
+            to: Coord::new(to, 0),
+        };
+        assert!(!board.is_legal(relocate(0, 2)));
+
+        board.play(relocate(1, 2)).unwrap();
+        assert_eq!(row(&board), vec![e, w, w, w, b]);
+        // the stone pulled from the first cell left room for any black stone
+        assert!(board.is_legal(relocate(4, 0)));
+        board.play(relocate(4, 0)).unwrap();
+        assert_eq!(row(&board), vec![b, w, w, w, e]);
+
+        board.pop_history();
+        assert!(board.is_legal(relocate(4, 0)));
+        board.pop_history();
+        assert_eq!(row(&board), vec![b, w, e, w, b]);
+        assert_eq!(board.turn, Team::White);
+    }
+
+    #[test]
+    fn test_relocation_when_stuck() {
+        let mut board = Board::new(1, 3);
+        board.topology = Topology::Plane;
+        board.rules.relocation = true;
+        board.board[0][0] = Team::White;
+        board.board[2][0] = Team::Black;
+        let relocate = |from, to| Move::Relocate {
+            from: Coord::new(from, 0),
+            to: Coord::new(to, 0),
+        };
+        board.play(relocate(0, 1)).unwrap();
+        // black can't move, but white still can
+        assert_eq!(board.legal_moves(), vec![Move::Pass]);
+        assert_eq!(board.result(), None);
+        board.play(Move::Pass).unwrap();
+        assert!(board.is_legal(relocate(1, 0)));
+
+        // once no team can move, the game ends
+        board.layout.toggle_wall(Coord::new(0, 0), Coord::new(1, 0));
+        assert!(board.legal_moves().is_empty());
+        assert_eq!(board.result(), Some(GameResult::Draw));
+    }
+
+    #[test]
+    fn test_komi() {
+        let mut board = Board::new(5, 5);
+        board.rules.komi = 2;
+        board.play(place(0, 0)).unwrap();
+        assert_eq!(board.scores(), vec![(Team::White, 1), (Team::Black, 2)]);
+        board.play(Move::Pass).unwrap();
+        board.play(Move::Pass).unwrap();
+        assert_eq!(board.result(), Some(GameResult::Win(Team::Black)));
+    }
+
+    #[test]
+    fn test_play_and_undo() {
+        let mut board = Board::new(5, 5);
+        board.play(place(0, 0)).unwrap();
+        assert_eq!(board.play(place(0, 0)), None);
+        assert_eq!(board.moves().len(), 1);
+        board.pop_history();
+        assert_eq!(board.board[0][0], Team::Empty);
+        assert_eq!(board.turn, Team::White);
+    }
+
+    #[test]
+    fn test_redo() {
+        let mut board = Board::new(5, 5);
+        board.play(place(0, 0)).unwrap();
+        board.play(place(2, 2)).unwrap();
+        let position = board.board.clone();
+        board.pop_history();
+        board.pop_history();
+        board.redo().unwrap();
+        board.redo().unwrap();
+        assert_eq!(board.board, position);
+        assert_eq!(board.redo(), None);
+
+        board.pop_history();
+        board.play(place(3, 3)).unwrap();
+        assert_eq!(board.redo(), None);
+        assert_eq!(board.moves(), vec![place(0, 0), place(3, 3)]);
+        board.pop_history();
+        assert_eq!(
+            board.variations.continuations(),
+            vec![place(2, 2), place(3, 3)]
+        );
+    }
+
+    #[test]
+    fn test_reach_per_axis() {
+        // a wide board, to pull along the rows further than the number of rows
+        let pull_from = |reach, x| {
+            let mut board = Board::new(3, 8);
+            board.rules.reach = reach;
+            board.board[x as usize][0] = Team::Black;
+            board.apply(place(0, 0)).unwrap().pulls
+        };
+        let pulled = |x| {
+            vec![Pull {
+                from: Coord::new(x, 0),
+                to: Coord::new(1, 0),
+                team: Team::Black,
+            }]
+        };
+        assert_eq!(pull_from(Reach::Unlimited, 4), pulled(4));
+        assert_eq!(pull_from(Reach::Cells(3), 4), vec![]);
+        assert_eq!(pull_from(Reach::Cells(3), 3), pulled(3));
+    }
+
+    #[test]
+    fn test_magnetism() {
+        let pulls = |magnetism| {
+            let mut board = Board::new(5, 5);
+            board.rules.magnetism = magnetism;
+            board.board[3][3] = Team::Black;
+            board.board[3][0] = Team::Black;
+            board.apply(place(0, 0)).unwrap().pulls
+        };
+        let diagonal = Pull {
+            from: Coord::new(3, 3),
+            to: Coord::new(4, 4),
+            team: Team::Black,
+        };
+        let orthogonal = Pull {
+            from: Coord::new(3, 0),
+            to: Coord::new(4, 0),
+            team: Team::Black,
+        };
+        assert_eq!(pulls(Magnetism::Orthogonal), vec![orthogonal]);
+        assert_eq!(pulls(Magnetism::Diagonal), vec![diagonal]);
+        assert_eq!(
+            pulls(Magnetism::Omnidirectional),
+            vec![orthogonal, diagonal]
+        );
+    }
+
+    #[test]
+    fn test_diagonal_wraps_rectangular_torus() {
+        // the diagonal goes through all the cells of a 2x3 torus before coming back
+        let mut board = Board::new(2, 3);
+        board.rules.magnetism = Magnetism::Diagonal;
+        board.board[2][1] = Team::Black;
+        let outcome = board.apply(place(0, 0)).unwrap();
+        assert_eq!(
+            outcome.pulls,
+            vec![Pull {
+                from: Coord::new(2, 1),
+                to: Coord::new(1, 1),
+                team: Team::Black,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_hexagonal_axes() {
+        let mut board = Board::new(5, 5);
+        board.topology = Topology::HexagonalTorus;
+        board.rules.magnetism = Magnetism::Diagonal;
+        board.board[4][0] = Team::Black;
+        board.board[4][4] = Team::Black;
+        let outcome = board.apply(place(2, 2)).unwrap();
+        assert_eq!(
+            outcome.pulls,
+            vec![Pull {
+                from: Coord::new(4, 0),
+                to: Coord::new(3, 1),
+                team: Team::Black,
+            }]
+        );
+        assert_eq!(board.board[4][4], Team::Black);
+    }
+
+    #[test]
+    fn test_blocked_cells_and_walls() {
+        let mut board = Board::new(1, 6);
+        board.topology = Topology::Plane;
+        let mut layout = Layout::empty(1, 6);
+        layout.toggle_stone(Coord::new(5, 0), Team::Black);
+        layout.toggle_blocked(Coord::new(2, 0));
+        board.set_layout(layout.clone());
+        assert_eq!(board.board[5][0], Team::Black);
+        assert!(!board.is_legal(place(2, 0)));
+        assert_eq!(board.apply(place(0, 0)).unwrap().pulls, vec![]);
+
+        layout.toggle_blocked(Coord::new(2, 0));
+        layout.toggle_wall(Coord::new(3, 0), Coord::new(4, 0));
+        board.set_layout(layout);
+        assert_eq!(board.board[0][0], Team::Empty);
+        assert_eq!(board.apply(place(0, 0)).unwrap().pulls, vec![]);
+    }
+
+    #[test]
+    fn test_repetition() {
+        for repetition in [Repetition::Superko, Repetition::Draw] {
+            let mut board = Board::new(3, 3);
+            board.set_rules(Rules {
+                repetition,
+                ..Rules::default()
+            });
+            let start = board.board.clone();
+            board.play(place(0, 0)).unwrap();
+            board.play(place(1, 1)).unwrap();
+            // placing stones can't repeat a position by itself, so the cells are rewound by hand
+            board.board = start;
+            board.turn = Team::White;
+            assert!(board.is_legal(place(2, 2)));
+            if repetition == Repetition::Superko {
+                assert!(!board.is_legal(place(0, 0)));
+            } else {
+                board.play(place(0, 0)).unwrap();
+                assert_eq!(board.result(), Some(GameResult::Draw));
+                assert!(board.legal_moves().is_empty());
+                board.pop_history();
+                assert_eq!(board.result(), None);
             }
         }
     }
-    (whites, blacks)
+
+    #[test]
+    fn test_repel() {
+        let mut board = Board::new(1, 8);
+        board.topology = Topology::Plane;
+        board.rules.polarity = PolarityRule::Repel;
+        board.board[2][0] = Team::Black;
+        board.board[6][0] = Team::White;
+        let outcome = board.apply(place(0, 0)).unwrap();
+        assert_eq!(
+            outcome.pulls,
+            vec![Pull {
+                from: Coord::new(2, 0),
+                to: Coord::new(5, 0),
+                team: Team::Black,
+            }]
+        );
+        assert_eq!(board.board[5][0], Team::White);
+        board.revert(&outcome);
+        assert_eq!(board.board[2][0], Team::Black);
+        assert_eq!(board.board[5][0], Team::Empty);
+    }
+
+    #[test]
+    fn test_three_teams() {
+        let mut board = Board::new(1, 6);
+        board.topology = Topology::Plane;
+        let turn_order = TurnOrder::new(&[Team::White, Team::Red, Team::Black]).unwrap();
+        board.set_rules(Rules {
+            turn_order,
+            ..Rules::default()
+        });
+        board.board[2][0] = Team::Red;
+        board.board[5][0] = Team::Black;
+        board.play(place(0, 0)).unwrap();
+        assert_eq!(board.turn, Team::Red);
+        let outcome = board.play(place(3, 0)).unwrap();
+        let pulled = outcome
+            .pulls
+            .iter()
+            .map(|pull| pull.team)
+            .collect::<Vec<_>>();
+        assert_eq!(pulled, vec![Team::Black, Team::White]);
+        assert_eq!(board.turn, Team::Black);
+        assert_eq!(
+            board.scores(),
+            vec![(Team::White, 1), (Team::Red, 3), (Team::Black, 0)]
+        );
+        while let Some(mv) = board.legal_moves().first() {
+            board.play(*mv).unwrap();
+        }
+        assert_eq!(board.result(), Some(GameResult::Win(Team::Red)));
+    }
+
+    #[test]
+    fn test_turn_order_permutations() {
+        let first = TurnOrder::with_count(3).unwrap();
+        let mut turn_order = first;
+        let mut names = Vec::new();
+        for _ in 0..6 {
+            names.push(turn_order.name());
+            assert_eq!(TurnOrder::from_name(&turn_order.name()), Some(turn_order));
+            turn_order = turn_order.next_permutation();
+        }
+        assert_eq!(turn_order, first);
+        assert_eq!(names[1], "white, red, black");
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 6);
+        assert_eq!(TurnOrder::from_name("white, white"), None);
+        assert_eq!(TurnOrder::with_count(1), None);
+    }
+
+    #[test]
+    fn test_turn_of_a_team_not_playing() {
+        let mut board = Board::from_position("2x2 2/2 r").unwrap();
+        let outcome = board.play(place(0, 0)).unwrap();
+        assert_eq!(outcome.team, Team::Red);
+        assert_eq!(board.turn, Team::White);
+        board.pop_history();
+        assert_eq!(board.turn, Team::Red);
+    }
+
+    #[test]
+    fn test_player_chooses_polarity() {
+        let mut board = Board::new(1, 5);
+        board.topology = Topology::Plane;
+        board.rules.polarity = PolarityRule::PlayerChoice;
+        board.board[3][0] = Team::Black;
+        assert!(!board.is_legal(place(0, 0)));
+        assert_eq!(board.legal_moves().len(), 9);
+        let mut attracting = board.clone();
+        let mv = board.place_move(Coord::new(0, 0), Polarity::Attract);
+        assert_eq!(attracting.apply(mv).unwrap().pulls[0].to, Coord::new(1, 0));
+        let mv = board.place_move(Coord::new(0, 0), Polarity::Repel);
+        assert_eq!(board.apply(mv).unwrap().pulls[0].to, Coord::new(4, 0));
+    }
+
+    #[test]
+    fn test_reach_names() {
+        for reach in [Reach::Unlimited, Reach::Cells(2), Reach::Cells(5)] {
+            assert_eq!(Reach::from_name(&reach.name()), Some(reach));
+        }
+        assert_eq!(Reach::from_name("1"), None);
+        assert_eq!(Reach::Cells(5).next(), Reach::Unlimited);
+    }
+
+    #[test]
+    fn test_undo_reverts_pulls() {
+        let mut board = Board::new(4, 5);
+        let mut positions = Vec::new();
+        let mut pulls = 0;
+        // fill the board in a scattered order, so that there are pulls in all directions
+        for i in 0..20 {
+            let moves = board.legal_moves();
+            if moves.is_empty() {
+                break;
+            }
+            positions.push((board.board.clone(), board.turn));
+            pulls += board.play(moves[i * 7 % moves.len()]).unwrap().pulls.len();
+        }
+        assert!(pulls > 0);
+        while let Some((position, turn)) = positions.pop() {
+            board.pop_history();
+            assert_eq!(board.board, position);
+            assert_eq!(board.turn, turn);
+        }
+    }
 }
@@ -0,0 +1,125 @@
+use crate::board::{Board, Board2d, Move, Team};
+use crate::bots::alpha_beta::AlphaBeta;
+use crate::bots::mcts::{Difficulty, Mcts};
+use crate::AnyError;
+use std::sync::mpsc::{Receiver, Sender};
+
+const ALPHA_BETA_TIME_BUDGET_SECONDS: f64 = 1.0;
+
+pub trait Bot: Send {
+    /// Returns None if there is no legal move.
+    fn choose_move(&mut self, board: &Board) -> Option<Move>;
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BotKind {
+    AlphaBeta,
+    Mcts(Difficulty),
+}
+impl BotKind {
+    pub fn name(&self) -> String {
+        match self {
+            BotKind::AlphaBeta => "alpha-beta".to_string(),
+            BotKind::Mcts(difficulty) => format!("MCTS {}", difficulty.name()),
+        }
+    }
+    #[must_use]
+    pub fn next(&self) -> BotKind {
+        match self {
+            BotKind::AlphaBeta => BotKind::Mcts(Difficulty::Easy),
+            BotKind::Mcts(Difficulty::Easy) => BotKind::Mcts(Difficulty::Medium),
+            BotKind::Mcts(Difficulty::Medium) => BotKind::Mcts(Difficulty::Hard),
+            BotKind::Mcts(Difficulty::Hard) => BotKind::AlphaBeta,
+        }
+    }
+    pub fn create(&self) -> Box<dyn Bot> {
+        match self {
+            BotKind::AlphaBeta => Box::new(AlphaBeta::new(ALPHA_BETA_TIME_BUDGET_SECONDS)),
+            BotKind::Mcts(difficulty) => Box::new(Mcts::new(*difficulty)),
+        }
+    }
+}
+
+/// Runs a bot outside of the render thread, so that the game stays responsive while it thinks.
+pub struct BotPlayer {
+    to_bot: Sender<Board>,
+    from_bot: Receiver<Option<Move>>,
+    /// The position the bot is thinking about, if any.
+    pending: Option<(Board2d, Team)>,
+    #[cfg(target_arch = "wasm32")]
+    bot: Box<dyn Bot>,
+    #[cfg(target_arch = "wasm32")]
+    from_local: Receiver<Board>,
+    #[cfg(target_arch = "wasm32")]
+    to_local: Sender<Option<Move>>,
+}
+
+impl BotPlayer {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(bot: Box<dyn Bot>) -> Self {
+        let (to_bot, from_local) = std::sync::mpsc::channel::<Board>();
+        let (to_local, from_bot) = std::sync::mpsc::channel::<Option<Move>>();
+        std::thread::spawn(move || bot_thread(bot, from_local, to_local));
+        Self {
+            to_bot,
+            from_bot,
+            pending: None,
+        }
+    }
+
+    /// There are no threads in the browser, so the bot thinks during the frame it's asked to.
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(bot: Box<dyn Bot>) -> Self {
+        let (to_bot, from_local) = std::sync::mpsc::channel::<Board>();
+        let (to_local, from_bot) = std::sync::mpsc::channel::<Option<Move>>();
+        Self {
+            to_bot,
+            from_bot,
+            pending: None,
+            bot,
+            from_local,
+            to_local,
+        }
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Asks the bot for a move when it's its turn, and plays it once it arrives. Moves that
+    /// arrive after the board changed (e.g. because of an undo) are discarded.
+    pub fn update(&mut self, board: &mut Board, bot_team: Team) -> Result<(), AnyError> {
+        if let Ok(mv) = self.from_bot.try_recv() {
+            let still_valid = self
+                .pending
+                .take()
+                .map(|(position, turn)| position == board.board && turn == board.turn)
+                .unwrap_or(false);
+            if still_valid {
+                if let Some(mv) = mv {
+                    board.play(mv);
+                }
+            }
+        }
+        if self.pending.is_none() && board.turn == bot_team && !board.is_terminal() {
+            let mut position = board.clone();
+            position.variations.clear();
+            self.pending = Some((board.board.clone(), board.turn));
+            self.to_bot.send(position)?;
+            #[cfg(target_arch = "wasm32")]
+            if let Ok(position) = self.from_local.try_recv() {
+                self.to_local.send(self.bot.choose_move(&position))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn bot_thread(mut bot: Box<dyn Bot>, from_local: Receiver<Board>, to_local: Sender<Option<Move>>) {
+    while let Ok(board) = from_local.recv() {
+        if to_local.send(bot.choose_move(&board)).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::board::{Board, Move, Team};
+use crate::bot_player::Bot;
+use macroquad::miniquad::date::now;
+use std::cmp::Reverse;
+
+const WIN: i32 = 1_000_000;
+
+/// Negamax with alpha-beta pruning and iterative deepening. Keeps the best move of the deepest
+/// search that finished before the time budget ran out.
+pub struct AlphaBeta {
+    pub time_budget_seconds: f64,
+}
+
+impl AlphaBeta {
+    pub fn new(time_budget_seconds: f64) -> Self {
+        Self {
+            time_budget_seconds,
+        }
+    }
+}
+
+impl Bot for AlphaBeta {
+    fn choose_move(&mut self, board: &Board) -> Option<Move> {
+        let deadline = now() + self.time_budget_seconds;
+        let mut children = ordered_children(board);
+        let mut best = children.first()?.1;
+        let max_depth = children.len() as u32;
+        for depth in 1..=max_depth {
+            match search_root(&children, board.turn, depth, deadline) {
+                Some((index, _score)) => {
+                    best = children[index].1;
+                    // the best move of this iteration is searched first in the next one
+                    let best_child = children.remove(index);
+                    children.insert(0, best_child);
+                }
+                None => break,
+            }
+        }
+        Some(best)
+    }
+}
+
+/// Returns the index of the best child and its score, or None if the time ran out.
+fn search_root(
+    children: &[(Board, Move, usize)],
+    turn: Team,
+    depth: u32,
+    deadline: f64,
+) -> Option<(usize, i32)> {
+    let mut alpha = -WIN - 1;
+    let beta = WIN + 1;
+    let mut best_index = 0;
+    for (index, (child, _mv, _pulls)) in children.iter().enumerate() {
+        let score = child_score(turn, child, depth - 1, alpha, beta, deadline)?;
+        if score > alpha {
+            alpha = score;
+            best_index = index;
+        }
+    }
+    Some((best_index, alpha))
+}
+
+/// The score is from the point of view of the team in turn. Returns None if the time ran out.
+fn negamax(board: &Board, depth: u32, mut alpha: i32, beta: i32, deadline: f64) -> Option<i32> {
+    if now() > deadline {
+        return None;
+    }
+    if depth == 0 {
+        return Some(evaluate(board));
+    }
+    let children = ordered_children(board);
+    if children.is_empty() {
+        return Some(evaluate(board));
+    }
+    for (child, _mv, _pulls) in &children {
+        let score = child_score(board.turn, child, depth - 1, alpha, beta, deadline)?;
+        if score >= beta {
+            return Some(beta);
+        }
+        alpha = alpha.max(score);
+    }
+    Some(alpha)
+}
+
+/// The score of the child from the point of view of the team in turn at the parent, which
+/// stays in turn when it places two stones.
+fn child_score(
+    turn: Team,
+    child: &Board,
+    depth: u32,
+    alpha: i32,
+    beta: i32,
+    deadline: f64,
+) -> Option<i32> {
+    if child.turn == turn {
+        negamax(child, depth, alpha, beta, deadline)
+    } else {
+        Some(-negamax(child, depth, -beta, -alpha, deadline)?)
+    }
+}
+
+/// Stone difference between the team in turn and its strongest opponent, or a big number if the
+/// game is over. A finished game is scored by its winner, not by the stones, because lines,
+/// goals and repetitions can end it regardless of who has more.
+fn evaluate(board: &Board) -> i32 {
+    if board.is_terminal() {
+        return match board.winner() {
+            Some(team) if team == board.turn => WIN,
+            Some(_) => -WIN,
+            None => 0,
+        };
+    }
+    let best_opponent = board
+        .scores()
+        .iter()
+        .filter(|(team, _score)| *team != board.turn)
+        .map(|(_team, score)| *score)
+        .max()
+        .unwrap_or(0);
+    board.score(board.turn) - best_opponent
+}
+
+/// All the positions reachable in one move, trying first the moves that pull more stones.
+fn ordered_children(board: &Board) -> Vec<(Board, Move, usize)> {
+    let mut children = Vec::new();
+    for mv in board.legal_moves() {
+        let mut child = board.clone();
+        if let Some(outcome) = child.apply(mv) {
+            children.push((child, mv, outcome.pulls.len()));
+        }
+    }
+    children.sort_by_key(|(_child, _mv, pulls)| Reverse(*pulls));
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Coord, Magnetism, StonesPerTurn, Team, Topology, WinCondition};
+
+    #[test]
+    fn test_only_move() {
+        let mut board = Board::new(1, 3);
+        board.topology = Topology::Plane;
+        board.play(Move::Place(Coord::new(0, 0))).unwrap();
+        board.play(Move::Place(Coord::new(1, 0))).unwrap();
+        let mv = AlphaBeta::new(0.1).choose_move(&board);
+        assert_eq!(mv, Some(Move::Place(Coord::new(2, 0))));
+    }
+
+    #[test]
+    fn test_no_move_when_finished() {
+        let mut board = Board::new(1, 1);
+        board.play(Move::Place(Coord::new(0, 0))).unwrap();
+        assert_eq!(AlphaBeta::new(0.1).choose_move(&board), None);
+    }
+
+    #[test]
+    fn test_prefers_pulling() {
+        let mut board = Board::new(5, 5);
+        board.board[1][2] = Team::Black;
+        board.board[1][3] = Team::Black;
+        board.board[0][0] = Team::White;
+        let children = ordered_children(&board);
+        let (index, score) = search_root(&children, board.turn, 1, now() + 10.0).unwrap();
+        assert_eq!(score, 4);
+        assert_eq!(children[index].2, 2);
+    }
+
+    #[test]
+    fn test_follows_magnetism() {
+        let mut board = Board::new(5, 5);
+        board.rules.magnetism = Magnetism::Diagonal;
+        board.board[2][2] = Team::Black;
+        let children = ordered_children(&board);
+        let (index, score) = search_root(&children, board.turn, 1, now() + 10.0).unwrap();
+        assert_eq!(score, 2);
+        assert_eq!(children[index].2, 1);
+    }
+
+    #[test]
+    fn test_second_placement_keeps_the_turn() {
+        let mut board = Board::new(1, 5);
+        board.topology = Topology::Plane;
+        board.rules.stones_per_turn = StonesPerTurn::Two;
+        board.play(Move::Place(Coord::new(0, 0))).unwrap();
+        let children = ordered_children(&board);
+        let (_index, score) = search_root(&children, board.turn, 2, now() + 10.0).unwrap();
+        // black places both stones before white can answer
+        assert!(score > 0);
+    }
+
+    #[test]
+    fn test_completes_line_with_fewer_stones() {
+        let mut board = Board::from_position("3x7 ww1#b#b/b#b#b#b/1#1#1#1 w").unwrap();
+        board.topology = Topology::Plane;
+        board.rules.win = WinCondition::Line(3);
+        let mv = AlphaBeta::new(0.1).choose_move(&board);
+        assert_eq!(mv, Some(Move::Place(Coord::new(2, 0))));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::board::{Board, Move, Team};
+use crate::bot_player::Bot;
+use macroquad::miniquad::date::now;
+
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+    pub fn playouts(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 300,
+            Difficulty::Medium => 3000,
+            Difficulty::Hard => 20000,
+        }
+    }
+    /// How much the final choice deviates from the most visited move. 0 always picks the most
+    /// visited move, 1 picks moves proportionally to their visits.
+    pub fn temperature(&self) -> f64 {
+        match self {
+            Difficulty::Easy => 1.0,
+            Difficulty::Medium => 0.3,
+            Difficulty::Hard => 0.0,
+        }
+    }
+}
+
+/// Monte Carlo tree search with UCT selection and uniformly random playouts.
+pub struct Mcts {
+    pub playouts: u32,
+    pub temperature: f64,
+    rng: Rng,
+}
+
+impl Mcts {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self::new_with_seed(difficulty, now().to_bits())
+    }
+    pub fn new_with_seed(difficulty: Difficulty, seed: u64) -> Self {
+        Self {
+            playouts: difficulty.playouts(),
+            temperature: difficulty.temperature(),
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+struct Node {
+    board: Board,
+    mv: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Move>,
+    visits: u32,
+    /// Wins of the team that made the move leading to this node. Draws count as half a win.
+    wins: f64,
+}
+
+impl Node {
+    fn new(board: Board, mv: Option<Move>, parent: Option<usize>) -> Self {
+        let untried = board.legal_moves();
+        Self {
+            board,
+            mv,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+    fn uct(&self, parent_visits: u32) -> f64 {
+        let visits = self.visits as f64;
+        self.wins / visits + EXPLORATION * ((parent_visits as f64).ln() / visits).sqrt()
+    }
+}
+
+impl Bot for Mcts {
+    fn choose_move(&mut self, board: &Board) -> Option<Move> {
+        let mut root = board.clone();
+        root.variations.clear();
+        let mut nodes = vec![Node::new(root, None, None)];
+        if nodes[0].untried.is_empty() {
+            return None;
+        }
+        for _ in 0..self.playouts {
+            let leaf = self.select_and_expand(&mut nodes);
+            let winner = self.playout(nodes[leaf].board.clone());
+            backpropagate(&mut nodes, leaf, winner);
+        }
+        let root_children = &nodes[0].children;
+        let index = self.pick_by_visits(&nodes, root_children);
+        nodes[root_children[index]].mv
+    }
+}
+
+impl Mcts {
+    fn select_and_expand(&mut self, nodes: &mut Vec<Node>) -> usize {
+        let mut current = 0;
+        loop {
+            let node = &nodes[current];
+            if !node.untried.is_empty() {
+                let index = self.rng.below(node.untried.len());
+                let mv = nodes[current].untried.swap_remove(index);
+                let mut board = nodes[current].board.clone();
+                board.apply(mv);
+                nodes.push(Node::new(board, Some(mv), Some(current)));
+                let child = nodes.len() - 1;
+                nodes[current].children.push(child);
+                return child;
+            }
+            if node.children.is_empty() {
+                return current;
+            }
+            let parent_visits = node.visits;
+            current = *node
+                .children
+                .iter()
+                .max_by(|a, b| {
+                    let a = nodes[**a].uct(parent_visits);
+                    let b = nodes[**b].uct(parent_visits);
+                    a.total_cmp(&b)
+                })
+                .unwrap();
+        }
+    }
+
+    /// Plays random moves until the end, and returns the winner, if any. Passes only when there
+    /// is nothing else to do, because random passes would end most playouts too early. Moving
+    /// stones back and forth could go on forever, so playouts that take more than two moves per
+    /// cell count as draws.
+    fn playout(&mut self, mut board: Board) -> Option<Team> {
+        let max_moves = 2 * board.size_rows * board.size_columns;
+        for _ in 0..max_moves {
+            let mut moves = board.legal_moves();
+            if moves.is_empty() {
+                return board.winner();
+            }
+            if moves.len() > 1 {
+                moves.retain(|mv| *mv != Move::Pass);
+            }
+            board.apply(moves[self.rng.below(moves.len())]);
+        }
+        None
+    }
+
+    fn pick_by_visits(&mut self, nodes: &[Node], children: &[usize]) -> usize {
+        let most_visited = (0..children.len())
+            .max_by_key(|i| nodes[children[*i]].visits)
+            .unwrap();
+        if self.temperature <= 0.0 {
+            return most_visited;
+        }
+        let weights = children
+            .iter()
+            .map(|child| (nodes[*child].visits as f64).powf(1.0 / self.temperature))
+            .collect::<Vec<_>>();
+        let mut remaining = self.rng.unit() * weights.iter().sum::<f64>();
+        for (i, weight) in weights.iter().enumerate() {
+            if remaining < *weight {
+                return i;
+            }
+            remaining -= weight;
+        }
+        most_visited
+    }
+}
+
+fn backpropagate(nodes: &mut [Node], leaf: usize, winner: Option<Team>) {
+    let mut current = Some(leaf);
+    while let Some(index) = current {
+        let node = &mut nodes[index];
+        node.visits += 1;
+        if let Some(parent) = node.parent {
+            // the team in turn at the parent is the one that moved into this node
+            let mover = nodes[parent].board.turn;
+            let node = &mut nodes[index];
+            node.wins += match winner {
+                Some(team) if team == mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+        current = nodes[index].parent;
+    }
+}
+
+/// Xorshift generator, good enough to randomize the playouts.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Coord, Topology};
+
+    #[test]
+    fn test_only_move() {
+        let mut board = Board::new(1, 3);
+        board.topology = Topology::Plane;
+        board.play(Move::Place(Coord::new(0, 0))).unwrap();
+        board.play(Move::Place(Coord::new(1, 0))).unwrap();
+        let mv = Mcts::new_with_seed(Difficulty::Easy, 1).choose_move(&board);
+        assert_eq!(mv, Some(Move::Place(Coord::new(2, 0))));
+    }
+
+    #[test]
+    fn test_no_move_when_finished() {
+        let mut board = Board::new(1, 1);
+        board.play(Move::Place(Coord::new(0, 0))).unwrap();
+        let mv = Mcts::new_with_seed(Difficulty::Easy, 1).choose_move(&board);
+        assert_eq!(mv, None);
+    }
+
+    #[test]
+    fn test_legal_move() {
+        let board = Board::new_default_size();
+        let mv = Mcts::new_with_seed(Difficulty::Easy, 7).choose_move(&board);
+        assert!(board.is_legal(mv.unwrap()));
+    }
+}
@@ -0,0 +1,140 @@
+//! Custom maps: cells where no stone can be placed, walls between neighbouring cells, and
+//! stones that are on the board from the start. The magnets can't pull or push stones through
+//! blocked cells or across walls. Diagonal magnets go between the corners of the cells, so walls
+//! don't stop them.
+//!
+//! See [`crate::notation`] for the text format used to save and share maps.
+
+use crate::board::{Coord, Team};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Layout {
+    pub size_rows: i32,
+    pub size_columns: i32,
+    blocked: HashSet<Coord>,
+    walls: HashSet<Wall>,
+    stones: HashMap<Coord, Team>,
+}
+
+/// A wall between two neighbouring cells. The order of the cells doesn't matter.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Wall {
+    first: Coord,
+    second: Coord,
+}
+
+impl Wall {
+    pub fn new(a: Coord, b: Coord) -> Self {
+        if (a.y, a.x) <= (b.y, b.x) {
+            Self {
+                first: a,
+                second: b,
+            }
+        } else {
+            Self {
+                first: b,
+                second: a,
+            }
+        }
+    }
+    pub fn cells(&self) -> (Coord, Coord) {
+        (self.first, self.second)
+    }
+}
+
+impl Layout {
+    /// A map without any blocked cell, wall or stone.
+    pub fn empty(size_rows: i32, size_columns: i32) -> Self {
+        Self {
+            size_rows,
+            size_columns,
+            blocked: HashSet::new(),
+            walls: HashSet::new(),
+            stones: HashMap::new(),
+        }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        (self.size_rows, self.size_columns)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocked.is_empty() && self.walls.is_empty() && self.stones.is_empty()
+    }
+
+    pub fn is_blocked(&self, coord: Coord) -> bool {
+        self.blocked.contains(&coord)
+    }
+
+    pub fn has_wall(&self, a: Coord, b: Coord) -> bool {
+        self.walls.contains(&Wall::new(a, b))
+    }
+
+    /// The starting stone in the cell, or [`Team::Empty`].
+    pub fn stone(&self, coord: Coord) -> Team {
+        self.stones.get(&coord).copied().unwrap_or(Team::Empty)
+    }
+
+    /// The walls, sorted from the top left corner.
+    pub fn walls(&self) -> Vec<Wall> {
+        let mut walls = self.walls.iter().copied().collect::<Vec<_>>();
+        walls.sort_by_key(|wall| (wall.first.y, wall.first.x, wall.second.y, wall.second.x));
+        walls
+    }
+
+    pub fn stones(&self) -> impl Iterator<Item = (Coord, Team)> + '_ {
+        self.stones.iter().map(|(coord, team)| (*coord, *team))
+    }
+
+    /// Blocks the cell, removing its starting stone, or unblocks it if it was blocked.
+    pub fn toggle_blocked(&mut self, coord: Coord) {
+        if !self.blocked.remove(&coord) {
+            self.stones.remove(&coord);
+            self.blocked.insert(coord);
+        }
+    }
+
+    pub fn toggle_wall(&mut self, a: Coord, b: Coord) {
+        let wall = Wall::new(a, b);
+        if !self.walls.remove(&wall) {
+            self.walls.insert(wall);
+        }
+    }
+
+    /// Puts a starting stone of the team in the cell, unblocking it, or removes the stone if the
+    /// cell already had one of that team.
+    pub fn toggle_stone(&mut self, coord: Coord, team: Team) {
+        if self.stones.get(&coord) == Some(&team) {
+            self.stones.remove(&coord);
+        } else {
+            self.blocked.remove(&coord);
+            self.stones.insert(coord, team);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggles() {
+        let mut layout = Layout::empty(3, 3);
+        let coord = Coord::new(1, 2);
+        layout.toggle_stone(coord, Team::Black);
+        layout.toggle_blocked(coord);
+        assert!(layout.is_blocked(coord));
+        assert_eq!(layout.stone(coord), Team::Empty);
+        layout.toggle_stone(coord, Team::White);
+        assert!(!layout.is_blocked(coord));
+        assert_eq!(layout.stone(coord), Team::White);
+        layout.toggle_stone(coord, Team::White);
+        assert!(layout.is_empty());
+
+        layout.toggle_wall(Coord::new(0, 0), Coord::new(1, 0));
+        assert!(layout.has_wall(Coord::new(1, 0), Coord::new(0, 0)));
+        layout.toggle_wall(Coord::new(1, 0), Coord::new(0, 0));
+        assert!(layout.is_empty());
+    }
+}
@@ -12,14 +12,25 @@ pub mod scenes {
     pub mod loading;
     pub mod menu;
     pub mod server_waiting;
+    pub mod settings;
 }
 pub mod ui {
     pub mod button_trait;
     pub mod complex_button;
     pub mod counter;
 }
+pub mod bots {
+    pub mod alpha_beta;
+    pub mod mcts;
+}
 pub mod board;
+pub mod bot_player;
+pub mod layout;
+pub mod notation;
+pub mod record;
 pub mod remote_player;
+pub mod variations;
+pub mod zobrist;
 
 pub type AnyError = Box<dyn std::error::Error>;
 
@@ -1,5 +1,8 @@
 use macroquad::prelude::*;
+use orthomagnet::board::{Rules, Team, TurnOrder};
 use orthomagnet::remote_player::{connect, serve};
+use orthomagnet::scenes::game::Exit;
+use orthomagnet::scenes::loading::Textures;
 use orthomagnet::scenes::menu::Player;
 use orthomagnet::scenes::{game, loading, menu, server_waiting};
 use orthomagnet::AnyError;
@@ -18,27 +21,57 @@ async fn main() {
 async fn try_main() -> Result<(), AnyError> {
     let textures = loading::scene().await?;
     let enable_networking = false;
-    if !enable_networking {
-        game::scene(textures, Player::Local, None, None).await
-    } else {
-        if let Some(player) = menu::scene().await {
-            match player {
-                Player::Local => game::scene(textures, player, None, None).await,
-                Player::Server => {
-                    let (from_client_, to_client_) = serve();
-                    if server_waiting::scene(&from_client_, &to_client_).await {
-                        game::scene(textures, player, Some(from_client_), Some(to_client_)).await
-                    } else {
-                        Ok(())
-                    }
-                }
-                Player::Client => {
-                    let (from_server_, to_server_) = connect();
-                    game::scene(textures, player, Some(from_server_), Some(to_server_)).await
-                }
+    let mut rules = Rules::default();
+    let mut player = menu::scene(enable_networking, &mut rules).await;
+    while let Some(chosen) = player {
+        player = match play(textures, chosen, rules).await? {
+            Exit::Menu => menu::scene(enable_networking, &mut rules).await,
+            Exit::Quit => None,
+        };
+    }
+    Ok(())
+}
+
+async fn play(textures: Textures, player: Player, rules: Rules) -> Result<Exit, AnyError> {
+    // the rules are not sent over the network yet, so both sides play with the default ones
+    let network_rules = Rules::default();
+    match player {
+        Player::Local => game::scene(textures, player, rules, None, None).await,
+        Player::Ai(_) => {
+            // the bots only know how to play against a single opponent, and the handicap is
+            // meant to help the human, who plays white
+            let rules = Rules {
+                turn_order: TurnOrder::default(),
+                handicap_team: Team::White,
+                ..rules
+            };
+            game::scene(textures, player, rules, None, None).await
+        }
+        Player::Server => {
+            let (from_client_, to_client_) = serve();
+            if server_waiting::scene(&from_client_, &to_client_).await {
+                game::scene(
+                    textures,
+                    player,
+                    network_rules,
+                    Some(from_client_),
+                    Some(to_client_),
+                )
+                .await
+            } else {
+                Ok(Exit::Menu)
             }
-        } else {
-            Ok(())
+        }
+        Player::Client => {
+            let (from_server_, to_server_) = connect();
+            game::scene(
+                textures,
+                player,
+                network_rules,
+                Some(from_server_),
+                Some(to_server_),
+            )
+            .await
         }
     }
 }
@@ -0,0 +1,540 @@
+//! One-line position notation, similar to FEN in chess:
+//!
+//! `<rows>x<columns> <cells> <turn> <walls>`
+//!
+//! The cells are listed row by row from the top, separated by `/`. Each row goes from left to
+//! right with `w`, `b`, `r` or `g` for white, black, red or green stones, `#` for a blocked
+//! cell, and a number for a run of empty cells. The turn is the letter of the team to move. For
+//! example, a 3x4 board with a single black stone in the second cell of the second row and white
+//! to move is `3x4 4/1b2/4 w`. The walls are optional, each one written as the two cells it
+//! separates, like `a1|b1` (see below for the cell names).
+//!
+//! Maps (see [`crate::layout`]) use the same notation without the turn, and their stones are
+//! the starting stones: `3x4 #3/1b2/4 a1|b1`.
+//!
+//! Moves are written as the column letter followed by the row number, both counted from the top
+//! left corner like the position cells, so `a1` is the top left cell and `c2` is the third cell
+//! of the second row. After `z` the columns continue with `aa`, `ab`, etc. When the rules let
+//! the player choose the polarity of each stone, the move ends with `+` to attract or `-` to
+//! repel, like `c2-`.
+
+use crate::board::{Board, Coord, Move, Polarity, Team};
+use crate::layout::{Layout, Wall};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, PartialEq)]
+pub struct NotationError(pub String);
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for NotationError {}
+
+const BLOCKED: char = '#';
+const WALL_SEPARATOR: char = '|';
+const PASS: &str = "pass";
+const SWAP: &str = "swap";
+const RELOCATION_SEPARATOR: char = '>';
+/// The most rows or columns that a parsed board can have, so that a typo doesn't build a board
+/// too big to fit in memory.
+pub const MAX_SIZE: i32 = 100;
+
+fn error<T>(message: String) -> Result<T, NotationError> {
+    Err(NotationError(message))
+}
+
+impl Board {
+    pub fn to_position(&self) -> String {
+        let cells = format_cells(self.size(), |coord| {
+            if self.layout.is_blocked(coord) {
+                BLOCKED
+            } else {
+                team_to_char(self.get_team(coord))
+            }
+        });
+        let size = format!("{}x{}", self.size_rows, self.size_columns);
+        let mut parts = vec![size, cells, team_to_char(self.turn).to_string()];
+        parts.extend(self.layout.walls().into_iter().map(format_wall));
+        parts.join(" ")
+    }
+
+    /// Creates a board in the given position, with an empty history.
+    pub fn from_position(text: &str) -> Result<Board, NotationError> {
+        let parts = text.split_whitespace().collect::<Vec<_>>();
+        let [size, cells, turn, ref walls @ ..] = parts[..] else {
+            return error(format!(
+                "expected at least 3 parts separated by spaces (size, cells and turn), got {}",
+                parts.len()
+            ));
+        };
+        let (size_rows, size_columns) = parse_size(size)?;
+        let mut turn_chars = turn.chars();
+        let turn = match (
+            turn_chars.next().and_then(team_from_char),
+            turn_chars.next(),
+        ) {
+            (Some(team), None) => team,
+            _ => {
+                return error(format!(
+                    "the turn must be one of 'w', 'b', 'r' or 'g', got '{}'",
+                    turn
+                ))
+            }
+        };
+        let cells = parse_cells(cells, (size_rows, size_columns))?;
+        let walls = walls
+            .iter()
+            .map(|wall| parse_wall(wall, (size_rows, size_columns)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut board = Board::new(size_rows, size_columns);
+        board.turn = turn;
+        // the stones are part of the position, not of the starting layout
+        let mut layout = Layout::empty(size_rows, size_columns);
+        for (coord, team) in cells {
+            match team {
+                Some(team) => board.board[coord.x as usize][coord.y as usize] = team,
+                None => layout.toggle_blocked(coord),
+            }
+        }
+        for (a, b) in walls {
+            layout.toggle_wall(a, b);
+        }
+        board.layout = layout;
+        Ok(board)
+    }
+
+    /// Like [`parse_move`], but also checks that the move is inside this board.
+    pub fn parse_move(&self, text: &str) -> Result<Move, NotationError> {
+        let mv = parse_move(text)?;
+        let lifted = match mv {
+            Move::Relocate { from, .. } => Some(from),
+            _ => None,
+        };
+        let mut cells = lifted.into_iter().chain(mv.coord());
+        if cells.any(|coord| !self.contains(coord)) {
+            error(format!(
+                "the move '{}' is outside of the {}x{} board",
+                text, self.size_rows, self.size_columns
+            ))
+        } else {
+            Ok(mv)
+        }
+    }
+}
+
+impl Layout {
+    pub fn to_text(&self) -> String {
+        let cells = format_cells(self.size(), |coord| {
+            if self.is_blocked(coord) {
+                BLOCKED
+            } else {
+                team_to_char(self.stone(coord))
+            }
+        });
+        let size = format!("{}x{}", self.size_rows, self.size_columns);
+        let mut parts = vec![size, cells];
+        parts.extend(self.walls().into_iter().map(format_wall));
+        parts.join(" ")
+    }
+
+    pub fn from_text(text: &str) -> Result<Layout, NotationError> {
+        let parts = text.split_whitespace().collect::<Vec<_>>();
+        let [size, cells, ref walls @ ..] = parts[..] else {
+            return error(format!(
+                "expected at least 2 parts separated by spaces (size and cells), got {}",
+                parts.len()
+            ));
+        };
+        let (size_rows, size_columns) = parse_size(size)?;
+        let cells = parse_cells(cells, (size_rows, size_columns))?;
+        let walls = walls
+            .iter()
+            .map(|wall| parse_wall(wall, (size_rows, size_columns)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut layout = Layout::empty(size_rows, size_columns);
+        for (coord, team) in cells {
+            match team {
+                Some(team) => layout.toggle_stone(coord, team),
+                None => layout.toggle_blocked(coord),
+            }
+        }
+        for (a, b) in walls {
+            layout.toggle_wall(a, b);
+        }
+        Ok(layout)
+    }
+}
+
+pub fn format_move(mv: Move) -> String {
+    let (coord, polarity) = match mv {
+        Move::Place(coord) => (coord, ""),
+        Move::PlacePolarized(coord, Polarity::Attract) => (coord, "+"),
+        Move::PlacePolarized(coord, Polarity::Repel) => (coord, "-"),
+        Move::Pass => return PASS.to_string(),
+        Move::Swap => return SWAP.to_string(),
+        Move::Relocate { from, to } => {
+            let (from, to) = (format_coord(from), format_coord(to));
+            return format!("{}{}{}", from, RELOCATION_SEPARATOR, to);
+        }
+    };
+    format!("{}{}", format_coord(coord), polarity)
+}
+
+fn format_coord(Coord { x, y }: Coord) -> String {
+    format!("{}{}", column_name(x), row_name(y))
+}
+
+pub fn parse_move(text: &str) -> Result<Move, NotationError> {
+    let invalid = || {
+        error(format!(
+            "the move must look like <column letter><row number>, e.g. 'c4', or '{}', got '{}'",
+            PASS, text
+        ))
+    };
+    if text.eq_ignore_ascii_case(PASS) {
+        return Ok(Move::Pass);
+    } else if text.eq_ignore_ascii_case(SWAP) {
+        return Ok(Move::Swap);
+    } else if let Some((from, to)) = text.split_once(RELOCATION_SEPARATOR) {
+        return match (parse_coord(from), parse_coord(to)) {
+            (Some(from), Some(to)) => Ok(Move::Relocate { from, to }),
+            _ => invalid(),
+        };
+    }
+    let (coord_text, polarity) = if let Some(coord_text) = text.strip_suffix('+') {
+        (coord_text, Some(Polarity::Attract))
+    } else if let Some(coord_text) = text.strip_suffix('-') {
+        (coord_text, Some(Polarity::Repel))
+    } else {
+        (text, None)
+    };
+    match (parse_coord(coord_text), polarity) {
+        (Some(coord), None) => Ok(Move::Place(coord)),
+        (Some(coord), Some(polarity)) => Ok(Move::PlacePolarized(coord, polarity)),
+        (None, _) => invalid(),
+    }
+}
+
+/// Parses a cell like `c4`.
+fn parse_coord(text: &str) -> Option<Coord> {
+    let digits_start = text
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(text.len());
+    let (letters, digits) = text.split_at(digits_start);
+    if letters.is_empty() || digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let mut column: i32 = 0;
+    for letter in letters.to_ascii_lowercase().bytes() {
+        column = column
+            .checked_mul(26)?
+            .checked_add((letter - b'a') as i32 + 1)?;
+    }
+    match digits.parse::<i32>() {
+        Ok(row) if row > 0 => Some(Coord::new(column - 1, row - 1)),
+        _ => None,
+    }
+}
+
+/// `a` for the first column, `z` for the 26th, `aa` for the 27th, etc.
+pub fn column_name(x: i32) -> String {
+    let mut name = Vec::new();
+    let mut remaining = x + 1;
+    while remaining > 0 {
+        remaining -= 1;
+        name.push(b'a' + (remaining % 26) as u8);
+        remaining /= 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+/// `1` for the top row.
+pub fn row_name(y: i32) -> String {
+    (y + 1).to_string()
+}
+
+fn parse_size(size: &str) -> Result<(i32, i32), NotationError> {
+    let parse = |number: &str| number.parse::<i32>().ok().filter(|n| *n > 0);
+    if let Some((rows, columns)) = size.split_once('x') {
+        if let (Some(rows), Some(columns)) = (parse(rows), parse(columns)) {
+            if rows > MAX_SIZE || columns > MAX_SIZE {
+                return error(format!(
+                    "the size can be at most {}x{}, got '{}'",
+                    MAX_SIZE, MAX_SIZE, size
+                ));
+            }
+            return Ok((rows, columns));
+        }
+    }
+    error(format!(
+        "the size must look like <rows>x<columns>, got '{}'",
+        size
+    ))
+}
+
+/// Writes the cells row by row from the top, with runs of empty cells as numbers. `cell` returns
+/// the character of each cell, where `.` is empty.
+fn format_cells((size_rows, size_columns): (i32, i32), cell: impl Fn(Coord) -> char) -> String {
+    let mut rows = Vec::new();
+    for y in 0..size_rows {
+        let mut row = String::new();
+        let mut empty_run = 0;
+        for x in 0..size_columns {
+            match cell(Coord::new(x, y)) {
+                '.' => empty_run += 1,
+                c => {
+                    if empty_run > 0 {
+                        row += &empty_run.to_string();
+                        empty_run = 0;
+                    }
+                    row.push(c);
+                }
+            }
+        }
+        if empty_run > 0 {
+            row += &empty_run.to_string();
+        }
+        rows.push(row);
+    }
+    rows.join("/")
+}
+
+/// Reads the cells written by [`format_cells`], returning each cell that is not empty, with the
+/// team of its stone, or None if it's blocked.
+fn parse_cells(
+    cells: &str,
+    (size_rows, size_columns): (i32, i32),
+) -> Result<Vec<(Coord, Option<Team>)>, NotationError> {
+    let mut parsed = Vec::new();
+    let rows = cells.split('/').collect::<Vec<_>>();
+    if rows.len() != size_rows as usize {
+        return error(format!("expected {} rows, got {}", size_rows, rows.len()));
+    }
+    for (y, row) in rows.iter().enumerate() {
+        let row_number = y + 1;
+        let mut x: i32 = 0;
+        let too_long = || {
+            NotationError(format!(
+                "row {} has too many cells, expected {}",
+                row_number, size_columns
+            ))
+        };
+        let mut chars = row.chars().peekable();
+        while let Some(c) = chars.next() {
+            if let Some(digit) = c.to_digit(10) {
+                let mut run = digit as i32;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    run = run
+                        .checked_mul(10)
+                        .and_then(|run| run.checked_add(digit as i32))
+                        .ok_or_else(too_long)?;
+                    chars.next();
+                }
+                if run == 0 {
+                    return error(format!("row {} has an empty run of length 0", row_number));
+                }
+                x = x.checked_add(run).ok_or_else(too_long)?;
+            } else {
+                let team = if c == BLOCKED {
+                    None
+                } else {
+                    Some(team_from_char(c).ok_or_else(|| {
+                        NotationError(format!(
+                            "row {} has an invalid cell '{}', expected a team letter, '#' or a \
+                            number",
+                            row_number, c
+                        ))
+                    })?)
+                };
+                if x < size_columns {
+                    parsed.push((Coord::new(x, y as i32), team));
+                }
+                x = x.checked_add(1).ok_or_else(too_long)?;
+            }
+        }
+        if x != size_columns {
+            return error(format!(
+                "row {} has {} cells, expected {}",
+                row_number, x, size_columns
+            ));
+        }
+    }
+    Ok(parsed)
+}
+
+fn format_wall(wall: Wall) -> String {
+    let (a, b) = wall.cells();
+    let cell = |coord: Coord| format!("{}{}", column_name(coord.x), row_name(coord.y));
+    format!("{}{}{}", cell(a), WALL_SEPARATOR, cell(b))
+}
+
+fn parse_wall(
+    text: &str,
+    (size_rows, size_columns): (i32, i32),
+) -> Result<(Coord, Coord), NotationError> {
+    let cell = |cell_text: &str| match parse_move(cell_text) {
+        Ok(Move::Place(coord)) if coord.x < size_columns && coord.y < size_rows => Some(coord),
+        _ => None,
+    };
+    if let Some((a, b)) = text.split_once(WALL_SEPARATOR) {
+        if let (Some(a), Some(b)) = (cell(a), cell(b)) {
+            return Ok((a, b));
+        }
+    }
+    error(format!(
+        "the wall must look like <cell>|<cell> with cells inside the {}x{} board, e.g. 'c3|c4', \
+        got '{}'",
+        size_rows, size_columns, text
+    ))
+}
+
+fn team_to_char(team: Team) -> char {
+    match team {
+        Team::Empty => '.',
+        Team::White => 'w',
+        Team::Black => 'b',
+        Team::Red => 'r',
+        Team::Green => 'g',
+    }
+}
+
+fn team_from_char(c: char) -> Option<Team> {
+    Team::PLAYERS
+        .iter()
+        .copied()
+        .find(|team| team_to_char(*team) == c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut board = Board::new(3, 12);
+        board.board[1][1] = Team::Black;
+        board.board[11][2] = Team::White;
+        board.turn = Team::Black;
+        let position = board.to_position();
+        assert_eq!(position, "3x12 12/1b10/11w b");
+        let parsed = Board::from_position(&position).unwrap();
+        assert_eq!(parsed.board, board.board);
+        assert_eq!(parsed.turn, board.turn);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let message = |text| Board::from_position(text).err().unwrap().0;
+        assert_eq!(
+            message("3x4 4/4/4"),
+            "expected at least 3 parts separated by spaces (size, cells and turn), got 2"
+        );
+        assert_eq!(
+            message("3*4 4/4/4 w"),
+            "the size must look like <rows>x<columns>, got '3*4'"
+        );
+        assert_eq!(message("3x4 4/4 w"), "expected 3 rows, got 2");
+        assert_eq!(message("3x4 4/2w/4 w"), "row 2 has 3 cells, expected 4");
+        assert_eq!(
+            message("3x4 4/2x1/4 w"),
+            "row 2 has an invalid cell 'x', expected a team letter, '#' or a number"
+        );
+        assert_eq!(
+            message("3x4 4/4/4 x"),
+            "the turn must be one of 'w', 'b', 'r' or 'g', got 'x'"
+        );
+        assert_eq!(
+            message("1000000x1000000 1/1 w"),
+            "the size can be at most 100x100, got '1000000x1000000'"
+        );
+        assert_eq!(
+            message("3x4 99999999999/4/4 w"),
+            "row 1 has too many cells, expected 4"
+        );
+        assert_eq!(
+            message("3x4 2147483647w/4/4 w"),
+            "row 1 has too many cells, expected 4"
+        );
+    }
+
+    #[test]
+    fn test_walls_and_blocked_cells() {
+        let text = "3x4 #3/1b2/4 w a1|a2 c2|d2";
+        let board = Board::from_position(text).unwrap();
+        assert!(board.layout.is_blocked(Coord::new(0, 0)));
+        assert!(board.layout.has_wall(Coord::new(3, 1), Coord::new(2, 1)));
+        assert_eq!(board.board[1][1], Team::Black);
+        assert_eq!(board.layout.stone(Coord::new(1, 1)), Team::Empty);
+        assert_eq!(board.to_position(), text);
+
+        let map = "3x4 #3/1b2/4 a1|a2 c2|d2";
+        let layout = Layout::from_text(map).unwrap();
+        assert_eq!(layout.stone(Coord::new(1, 1)), Team::Black);
+        assert_eq!(layout.to_text(), map);
+        assert_eq!(
+            Layout::from_text("3x4 4/4/4 a1|e1").err().unwrap().0,
+            "the wall must look like <cell>|<cell> with cells inside the 3x4 board, e.g. \
+            'c3|c4', got 'a1|e1'"
+        );
+    }
+
+    #[test]
+    fn test_move_round_trip() {
+        for (x, y, text) in [(0, 0, "a1"), (2, 3, "c4"), (25, 9, "z10"), (26, 0, "aa1")] {
+            let mv = Move::Place(Coord::new(x, y));
+            assert_eq!(format_move(mv), text);
+            assert_eq!(parse_move(text), Ok(mv));
+        }
+        assert_eq!(parse_move("C4"), Ok(Move::Place(Coord::new(2, 3))));
+        for (polarity, text) in [(Polarity::Attract, "b1+"), (Polarity::Repel, "b1-")] {
+            let mv = Move::PlacePolarized(Coord::new(1, 0), polarity);
+            assert_eq!(format_move(mv), text);
+            assert_eq!(parse_move(text), Ok(mv));
+        }
+        assert_eq!(format_move(Move::Pass), "pass");
+        assert_eq!(parse_move("pass"), Ok(Move::Pass));
+        assert_eq!(format_move(Move::Swap), "swap");
+        assert_eq!(parse_move("swap"), Ok(Move::Swap));
+        let mv = Move::Relocate {
+            from: Coord::new(0, 1),
+            to: Coord::new(1, 1),
+        };
+        assert_eq!(format_move(mv), "a2>b2");
+        assert_eq!(parse_move("a2>b2"), Ok(mv));
+    }
+
+    #[test]
+    fn test_malformed_move() {
+        let message = |text| parse_move(text).err().unwrap().0;
+        let expected = |text| {
+            format!(
+                "the move must look like <column letter><row number>, e.g. 'c4', or 'pass', got '{}'",
+                text
+            )
+        };
+        for text in [
+            "",
+            "c",
+            "4",
+            "4c",
+            "c0",
+            "c4d",
+            "c-4",
+            "c4+-",
+            "c4>",
+            "c4>d5+",
+            "zzzzzzzz1",
+        ] {
+            assert_eq!(message(text), expected(text));
+        }
+        let board = Board::new(3, 2);
+        assert_eq!(board.parse_move("b3"), Ok(Move::Place(Coord::new(1, 2))));
+        assert_eq!(
+            board.parse_move("c3").err().unwrap().0,
+            "the move 'c3' is outside of the 3x2 board"
+        );
+    }
+}
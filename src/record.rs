@@ -0,0 +1,569 @@
+//! Text format to archive and share games. It has a header of tags followed by the numbered
+//! move list, where each move is written in the notation of [`crate::notation`]. Each playing
+//! team has a tag with the name of its player, like White and Black below. The Map tag is
+//! only written for games on a custom map, and the Position tag, with the position notation,
+//! only for games that didn't start from the usual empty board, like pasted positions:
+//!
+//! ```text
+//! [Rows "7"]
+//! [Columns "5"]
+//! [Topology "torus"]
+//! [Reach "unlimited"]
+//! [Magnetism "orthomagnet"]
+//! [Polarity "attract"]
+//! [Players "white, black"]
+//! [Repetition "allowed"]
+//! [Pie "no"]
+//! [Komi "0"]
+//! [Handicap "0"]
+//! [HandicapTeam "black"]
+//! [Win "majority"]
+//! [StonesPerTurn "one"]
+//! [Relocation "no"]
+//! [Map "7x5 5/5/1#3/5/3#1/5/5"]
+//! [Position "7x5 5/1w3/1#3/5/3#1/5/5 b"]
+//! [White "Human"]
+//! [Black "Computer (alpha-beta)"]
+//! [Date "2026-10-18"]
+//! [Result "white wins"]
+//!
+//! 1. c4 b2
+//! 2. a5
+//! ```
+
+use crate::board::{
+    Board, GameResult, Magnetism, Move, PolarityRule, Reach, Repetition, Rules, StonesPerTurn,
+    Team, Topology, TurnOrder, WinCondition, MAX_HANDICAP, MAX_KOMI,
+};
+use crate::layout::Layout;
+use crate::notation::{format_move, parse_move, MAX_SIZE};
+use crate::AnyError;
+use std::fmt::{Display, Formatter};
+
+pub const UNFINISHED: &str = "*";
+
+#[derive(Debug, PartialEq)]
+pub struct RecordError(pub String);
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for RecordError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameRecord {
+    pub size_rows: i32,
+    pub size_columns: i32,
+    pub topology: Topology,
+    pub rules: Rules,
+    pub layout: Layout,
+    /// The position before the first move, in the notation of [`crate::notation`], if it's not
+    /// the one that the rules and the layout start with.
+    pub start: Option<String>,
+    /// The name of the player of each team, in turn order.
+    pub players: Vec<(Team, String)>,
+    pub date: String,
+    pub result: Option<GameResult>,
+    pub moves: Vec<Move>,
+}
+
+impl GameRecord {
+    /// The teams missing from `players` are named after their colour.
+    pub fn from_board(board: &Board, players: &[(Team, &str)], date: &str) -> Self {
+        let mut start = board.clone();
+        while !start.moves().is_empty() {
+            start.pop_history();
+        }
+        let mut record = Self {
+            size_rows: board.size_rows,
+            size_columns: board.size_columns,
+            topology: board.topology,
+            rules: board.rules,
+            layout: board.layout.clone(),
+            start: None,
+            players: board
+                .rules
+                .turn_order
+                .teams()
+                .iter()
+                .map(|team| {
+                    let named = players.iter().find(|(named, _name)| named == team);
+                    let name = named.map_or(team_tag(*team), |(_team, name)| name.to_string());
+                    (*team, name)
+                })
+                .collect(),
+            date: date.to_string(),
+            result: board.result(),
+            moves: board.moves(),
+        };
+        let usual_start = record.usual_start();
+        if start.board != usual_start.board || start.turn != usual_start.turn {
+            record.start = Some(start.to_position());
+        }
+        record
+    }
+
+    /// The board that the rules and the layout start with.
+    fn usual_start(&self) -> Board {
+        let mut board = Board::new(self.size_rows, self.size_columns);
+        board.topology = self.topology;
+        board.set_rules(self.rules);
+        board.set_layout(self.layout.clone());
+        board
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut tag = |key: &str, value: &str| text += &format!("[{} \"{}\"]\n", key, value);
+        tag("Rows", &self.size_rows.to_string());
+        tag("Columns", &self.size_columns.to_string());
+        tag("Topology", self.topology.name());
+        tag("Reach", &self.rules.reach.name());
+        tag("Magnetism", self.rules.magnetism.name());
+        tag("Polarity", self.rules.polarity.name());
+        tag("Players", &self.rules.turn_order.name());
+        tag("Repetition", self.rules.repetition.name());
+        tag("Pie", yes_no(self.rules.pie));
+        tag("Komi", &self.rules.komi.to_string());
+        tag("Handicap", &self.rules.handicap.to_string());
+        tag("HandicapTeam", self.rules.handicap_team.name());
+        tag("Win", &self.rules.win.name());
+        tag("StonesPerTurn", self.rules.stones_per_turn.name());
+        tag("Relocation", yes_no(self.rules.relocation));
+        if !self.layout.is_empty() {
+            tag("Map", &self.layout.to_text());
+        }
+        if let Some(start) = &self.start {
+            tag("Position", start);
+        }
+        for (team, name) in &self.players {
+            tag(&team_tag(*team), name);
+        }
+        tag("Date", &self.date);
+        tag("Result", &result_to_text(self.result));
+        text += "\n";
+        for (i, pair) in self.moves.chunks(2).enumerate() {
+            text += &format!("{}.", i + 1);
+            for mv in pair {
+                text += &format!(" {}", format_move(*mv));
+            }
+            text += "\n";
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, RecordError> {
+        let mut size_rows = None;
+        let mut size_columns = None;
+        let mut layout = None;
+        let mut start_size = None;
+        let mut record = GameRecord {
+            size_rows: 0,
+            size_columns: 0,
+            topology: Topology::Torus,
+            rules: Rules::default(),
+            layout: Layout::empty(0, 0),
+            start: None,
+            players: Vec::new(),
+            date: String::new(),
+            result: None,
+            moves: Vec::new(),
+        };
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: String| RecordError(format!("line {}: {}", i + 1, message));
+            if line.is_empty() {
+                continue;
+            } else if line.starts_with('[') {
+                let (key, value) = parse_tag(line).ok_or_else(|| {
+                    error(format!("expected a tag like [Key \"value\"], got {}", line))
+                })?;
+                let parse_size = |value: &str| {
+                    value
+                        .parse::<i32>()
+                        .ok()
+                        .filter(|size| (1..=MAX_SIZE).contains(size))
+                        .ok_or_else(|| error(format!("invalid {} \"{}\"", key, value)))
+                };
+                match key {
+                    "Rows" => size_rows = Some(parse_size(value)?),
+                    "Columns" => size_columns = Some(parse_size(value)?),
+                    "Topology" => {
+                        record.topology = Topology::from_name(value)
+                            .ok_or_else(|| error(format!("unknown topology \"{}\"", value)))?
+                    }
+                    "Reach" => {
+                        record.rules.reach = Reach::from_name(value)
+                            .ok_or_else(|| error(format!("invalid reach \"{}\"", value)))?
+                    }
+                    "Magnetism" => {
+                        record.rules.magnetism = Magnetism::from_name(value)
+                            .ok_or_else(|| error(format!("unknown magnetism \"{}\"", value)))?
+                    }
+                    "Polarity" => {
+                        record.rules.polarity = PolarityRule::from_name(value)
+                            .ok_or_else(|| error(format!("unknown polarity \"{}\"", value)))?
+                    }
+                    "Players" => {
+                        record.rules.turn_order = TurnOrder::from_name(value)
+                            .ok_or_else(|| error(format!("invalid players \"{}\"", value)))?
+                    }
+                    "Repetition" => {
+                        record.rules.repetition = Repetition::from_name(value)
+                            .ok_or_else(|| error(format!("unknown repetition \"{}\"", value)))?
+                    }
+                    "Pie" => {
+                        record.rules.pie = parse_yes_no(value)
+                            .ok_or_else(|| error(format!("invalid pie \"{}\"", value)))?
+                    }
+                    "Relocation" => {
+                        record.rules.relocation = parse_yes_no(value)
+                            .ok_or_else(|| error(format!("invalid relocation \"{}\"", value)))?
+                    }
+                    "Komi" => {
+                        record.rules.komi = value
+                            .parse::<i32>()
+                            .ok()
+                            .filter(|komi| (0..=MAX_KOMI).contains(komi))
+                            .ok_or_else(|| error(format!("invalid komi \"{}\"", value)))?
+                    }
+                    "Handicap" => {
+                        record.rules.handicap = value
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|handicap| *handicap <= MAX_HANDICAP)
+                            .ok_or_else(|| error(format!("invalid handicap \"{}\"", value)))?
+                    }
+                    "HandicapTeam" => {
+                        record.rules.handicap_team = Team::from_name(value)
+                            .ok_or_else(|| error(format!("invalid handicap team \"{}\"", value)))?
+                    }
+                    "Win" => {
+                        record.rules.win = WinCondition::from_name(value)
+                            .ok_or_else(|| error(format!("invalid win condition \"{}\"", value)))?
+                    }
+                    "StonesPerTurn" => {
+                        record.rules.stones_per_turn =
+                            StonesPerTurn::from_name(value).ok_or_else(|| {
+                                error(format!("invalid stones per turn \"{}\"", value))
+                            })?
+                    }
+                    "Map" => {
+                        layout = Some(Layout::from_text(value).map_err(|e| error(e.0))?);
+                    }
+                    "Position" => {
+                        let start = Board::from_position(value).map_err(|e| error(e.0))?;
+                        start_size = Some(start.size());
+                        record.start = Some(value.to_string());
+                    }
+                    "Date" => record.date = value.to_string(),
+                    "Result" => {
+                        record.result = result_from_text(value)
+                            .ok_or_else(|| error(format!("unknown result \"{}\"", value)))?
+                    }
+                    _ => match team_from_tag(key) {
+                        Some(team) => record.players.push((team, value.to_string())),
+                        // unknown tags are ignored, to stay compatible with newer versions
+                        None => {}
+                    },
+                }
+            } else {
+                for token in line.split_whitespace() {
+                    if token.ends_with('.') {
+                        continue; // move number
+                    }
+                    let mv = parse_move(token).map_err(|e| error(e.0))?;
+                    record.moves.push(mv);
+                }
+            }
+        }
+        record.size_rows = size_rows.ok_or_else(|| RecordError("missing tag Rows".to_string()))?;
+        record.size_columns =
+            size_columns.ok_or_else(|| RecordError("missing tag Columns".to_string()))?;
+        let size = (record.size_rows, record.size_columns);
+        record.layout = match layout {
+            Some(layout) if layout.size() != size => {
+                return Err(RecordError(format!(
+                    "the map is {}x{} but the board is {}x{}",
+                    layout.size_rows, layout.size_columns, size.0, size.1
+                )))
+            }
+            Some(layout) => layout,
+            None => Layout::empty(size.0, size.1),
+        };
+        if let Some((rows, columns)) = start_size.filter(|start_size| *start_size != size) {
+            return Err(RecordError(format!(
+                "the position is {}x{} but the board is {}x{}",
+                rows, columns, size.0, size.1
+            )));
+        }
+        Ok(record)
+    }
+
+    /// Plays all the moves through the rules, failing at the first illegal one, or if the
+    /// recorded result doesn't match the final position.
+    pub fn replay(&self) -> Result<Board, RecordError> {
+        let mut board = match &self.start {
+            Some(start) => {
+                let mut board = Board::from_position(start).map_err(|e| RecordError(e.0))?;
+                board.topology = self.topology;
+                // setting the rules or the layout through the board would start a new game
+                board.rules = self.rules;
+                board.layout = self.layout.clone();
+                board
+            }
+            None => self.usual_start(),
+        };
+        for (i, mv) in self.moves.iter().enumerate() {
+            if board.play(*mv).is_none() {
+                return Err(RecordError(format!(
+                    "move {} ({}) is not legal",
+                    i + 1,
+                    format_move(*mv)
+                )));
+            }
+        }
+        if let Some(GameResult::Resignation(team)) = self.result {
+            board.resign(team);
+        }
+        if board.result() != self.result {
+            return Err(RecordError(format!(
+                "the recorded result is \"{}\" but the moves lead to \"{}\"",
+                result_to_text(self.result),
+                result_to_text(board.result())
+            )));
+        }
+        Ok(board)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), AnyError> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+    pub fn load(path: &str) -> Result<Self, AnyError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&text)?)
+    }
+}
+
+fn parse_tag(line: &str) -> Option<(&str, &str)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((key, value))
+}
+
+/// The key of the tag with the player of the team, which is its capitalized name.
+fn team_tag(team: Team) -> String {
+    let name = team.name();
+    name[..1].to_uppercase() + &name[1..]
+}
+
+fn team_from_tag(key: &str) -> Option<Team> {
+    Team::PLAYERS
+        .into_iter()
+        .find(|team| team_tag(*team) == key)
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn parse_yes_no(text: &str) -> Option<bool> {
+    match text {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn result_to_text(result: Option<GameResult>) -> String {
+    match result {
+        None => UNFINISHED.to_string(),
+        Some(GameResult::Win(team)) => format!("{} wins", team.name()),
+        Some(GameResult::Draw) => "draw".to_string(),
+        Some(GameResult::Resignation(team)) => format!("{} resigned", team.name()),
+    }
+}
+
+/// The outer None means the text is not a valid result.
+fn result_from_text(text: &str) -> Option<Option<GameResult>> {
+    Some(match text {
+        UNFINISHED => None,
+        "draw" => Some(GameResult::Draw),
+        _ => {
+            if let Some(name) = text.strip_suffix(" wins") {
+                Some(GameResult::Win(Team::from_name(name)?))
+            } else if let Some(name) = text.strip_suffix(" resigned") {
+                Some(GameResult::Resignation(Team::from_name(name)?))
+            } else {
+                return None;
+            }
+        }
+    })
+}
+
+/// Formats seconds since the Unix epoch as YYYY-MM-DD, in UTC.
+pub fn format_date(seconds_since_epoch: f64) -> String {
+    let days = (seconds_since_epoch / 86400.0).floor() as i64;
+    // civil-from-days algorithm from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{place, Coord};
+
+    #[test]
+    fn test_round_trip() {
+        let mut board = Board::new(4, 3);
+        board.topology = Topology::KleinBottle;
+        board.rules.reach = Reach::Cells(3);
+        board.rules.magnetism = Magnetism::Omnidirectional;
+        board.rules.pie = true;
+        board.rules.komi = 2;
+        board.rules.handicap = 1;
+        board.rules.handicap_team = Team::White;
+        board.rules.win = WinCondition::Line(4);
+        board.rules.relocation = true;
+        let mut layout = Layout::empty(4, 3);
+        layout.toggle_blocked(Coord::new(1, 2));
+        layout.toggle_wall(Coord::new(0, 0), Coord::new(0, 1));
+        board.set_layout(layout);
+        board.play(place(0, 0)).unwrap();
+        board.play(Move::Swap).unwrap();
+        board.play(place(2, 3)).unwrap();
+        board.play(place(0, 3)).unwrap();
+        board.resign(Team::Black);
+        let record = GameRecord::from_board(
+            &board,
+            &[(Team::White, "Alice"), (Team::Black, "Bob")],
+            "2026-10-18",
+        );
+        let text = record.to_text();
+        assert!(text.ends_with("\n1. a1 swap\n2. c4 a4\n"));
+        assert!(!text.contains("[Position"));
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed, record);
+        let replayed = parsed.replay().unwrap();
+        assert_eq!(replayed.board, board.board);
+        assert_eq!(replayed.result(), board.result());
+    }
+
+    #[test]
+    fn test_player_names() {
+        let mut board = Board::new(3, 3);
+        board.set_rules(Rules {
+            turn_order: TurnOrder::with_count(3).unwrap(),
+            ..board.rules
+        });
+        board.play(place(1, 1)).unwrap();
+        let record = GameRecord::from_board(&board, &[(Team::Red, "Carol")], "2026-10-18");
+        let text = record.to_text();
+        assert!(text.contains("[White \"White\"]\n[Black \"Black\"]\n[Red \"Carol\"]\n"));
+        assert_eq!(GameRecord::parse(&text).unwrap(), record);
+    }
+
+    #[test]
+    fn test_pasted_position() {
+        let mut board = Board::from_position("3x3 1w1/3/3 b").unwrap();
+        board.play(place(0, 2)).unwrap();
+        let record = GameRecord::from_board(
+            &board,
+            &[(Team::White, "Alice"), (Team::Black, "Bob")],
+            "2026-10-18",
+        );
+        let text = record.to_text();
+        assert!(text.contains("[Position \"3x3 1w1/3/3 b\"]\n"));
+        let parsed = GameRecord::parse(&text).unwrap();
+        assert_eq!(parsed, record);
+        let replayed = parsed.replay().unwrap();
+        assert_eq!(replayed.board, board.board);
+        assert_eq!(replayed.turn, board.turn);
+
+        let text = text.replace("[Rows \"3\"]", "[Rows \"4\"]");
+        assert_eq!(
+            GameRecord::parse(&text).err(),
+            Some(RecordError(
+                "the position is 3x3 but the board is 4x3".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let text = "[Rows \"3\"]\n[Columns \"3\"]\n\n1. a1 a1\n";
+        let record = GameRecord::parse(text).unwrap();
+        assert_eq!(
+            record.replay().err(),
+            Some(RecordError("move 2 (a1) is not legal".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let text = "[Rows \"3\"]\n[Columns \"three\"]\n";
+        assert_eq!(
+            GameRecord::parse(text).err(),
+            Some(RecordError("line 2: invalid Columns \"three\"".to_string()))
+        );
+        let text = "[Komi \"2147483647\"]\n";
+        assert_eq!(
+            GameRecord::parse(text).err(),
+            Some(RecordError(
+                "line 1: invalid komi \"2147483647\"".to_string()
+            ))
+        );
+        let text = "[Handicap \"1000000\"]\n";
+        assert_eq!(
+            GameRecord::parse(text).err(),
+            Some(RecordError(
+                "line 1: invalid handicap \"1000000\"".to_string()
+            ))
+        );
+        let text = "[Rows \"1000000\"]\n";
+        assert_eq!(
+            GameRecord::parse(text).err(),
+            Some(RecordError("line 1: invalid Rows \"1000000\"".to_string()))
+        );
+        let text = "[Rows \"3\"]\n[Columns \"3\"]\n1. zzzzzzzz1\n";
+        assert!(GameRecord::parse(text).is_err());
+        let text = "[Rows \"3\"]\n[Columns \"3\"]\n1. a;1\n";
+        assert_eq!(
+            GameRecord::parse(text).err(),
+            Some(RecordError(
+                "line 3: the move must look like <column letter><row number>, e.g. 'c4', \
+                 or 'pass', got 'a;1'"
+                    .to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0.0), "1970-01-01");
+        assert_eq!(format_date(1792281600.0), "2026-10-18");
+        assert_eq!(format_date(951782400.0), "2000-02-29");
+    }
+}
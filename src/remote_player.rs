@@ -13,6 +13,9 @@ pub const IP: &str = "127.0.0.1";
 pub enum Command {
     StoneHover { x: i32, y: i32 },
     StopStoneHover,
+    PlaceStone { x: i32, y: i32 },
+    Pass,
+    Resign,
     Connected,
 }
 
@@ -70,11 +73,11 @@ fn handle_stream(
     remote_name: &str,
 ) -> Result<(), AnyError> {
     let mut buf = [0; 500];
-    // TODO: make sure the buffer is clean everytime before reading. use the de_bin(offset, buf)
+    let mut pending = Vec::new();
     stream
         .set_read_timeout(Some(Duration::from_millis(10)))
         .unwrap();
-    while remote_to_local(remote_name, to_local, &mut stream, &mut buf)?
+    while remote_to_local(remote_name, to_local, &mut stream, &mut buf, &mut pending)?
         && local_to_remote(remote_name, from_local, &mut stream)?
     {}
     Ok(())
@@ -85,6 +88,7 @@ fn remote_to_local(
     to_local: &Sender<Command>,
     stream: &mut TcpStream,
     buf: &mut [u8; 500],
+    pending: &mut Vec<u8>,
 ) -> Result<bool, AnyError> {
     let should_continue = match stream.read(buf) {
         Ok(size) => {
@@ -92,12 +96,16 @@ fn remote_to_local(
                 println!("nothing more to read");
                 false
             } else {
-                let command = Command::deserialize_bin(buf)?;
-                println!(
-                    "read {} bytes from {} for command {:?}",
-                    size, remote_name, command
-                );
-                to_local.send(command)?;
+                // several commands can arrive in a single read, or a command can be split across
+                // reads, and losing a PlaceStone would make both boards diverge
+                pending.extend_from_slice(&buf[..size]);
+                while let Some((command, command_size)) = take_command(pending)? {
+                    println!(
+                        "read {} bytes from {} for command {:?}",
+                        command_size, remote_name, command
+                    );
+                    to_local.send(command)?;
+                }
                 true
             }
         }
@@ -130,8 +138,65 @@ fn local_to_remote(
     while let Ok(answer) = from_local.try_recv() {
         println!("sending command to {}: {:?}", remote_name, answer);
         let answer_buf = answer.serialize_bin();
-        stream.write(&answer_buf)?;
+        stream.write_all(&(answer_buf.len() as u32).to_le_bytes())?;
+        stream.write_all(&answer_buf)?;
         // TODO: there might be some things to do if the write fails
     }
     Ok(true)
 }
+
+/// Each command is sent after its size in bytes, so that a command is only decoded once all of
+/// it has arrived. Returns the first complete command and its size, removing it from `pending`.
+fn take_command(pending: &mut Vec<u8>) -> Result<Option<(Command, usize)>, AnyError> {
+    const PREFIX: usize = std::mem::size_of::<u32>();
+    // every command takes a few bytes, so anything bigger comes from a broken or hostile peer
+    const MAX_COMMAND_SIZE: usize = 64;
+    if pending.len() < PREFIX {
+        return Ok(None);
+    }
+    let mut prefix = [0; PREFIX];
+    prefix.copy_from_slice(&pending[..PREFIX]);
+    let size = u32::from_le_bytes(prefix) as usize;
+    if size > MAX_COMMAND_SIZE {
+        return Err(format!("the command size {} is too big", size).into());
+    }
+    if pending.len() < PREFIX + size {
+        return Ok(None);
+    }
+    let command = Command::deserialize_bin(&pending[PREFIX..PREFIX + size])?;
+    pending.drain(..PREFIX + size);
+    Ok(Some((command, size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(command: Command) -> Vec<u8> {
+        let bytes = command.serialize_bin();
+        let mut encoded = (bytes.len() as u32).to_le_bytes().to_vec();
+        encoded.extend(bytes);
+        encoded
+    }
+
+    #[test]
+    fn test_split_commands() {
+        let mut sent = encode(Command::PlaceStone { x: 3, y: 4 });
+        sent.extend(encode(Command::Pass));
+        let mut pending = sent[..6].to_vec();
+        assert!(take_command(&mut pending).unwrap().is_none());
+        pending.extend_from_slice(&sent[6..]);
+        let (first, _) = take_command(&mut pending).unwrap().unwrap();
+        assert!(matches!(first, Command::PlaceStone { x: 3, y: 4 }));
+        let (second, _) = take_command(&mut pending).unwrap().unwrap();
+        assert!(matches!(second, Command::Pass));
+        assert!(take_command(&mut pending).unwrap().is_none());
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_oversized_command() {
+        let mut pending = u32::MAX.to_le_bytes().to_vec();
+        assert!(take_command(&mut pending).is_err());
+    }
+}
@@ -1,22 +1,33 @@
-use crate::board::{Board, Team};
+use crate::board::{
+    Board, Coord, GameResult, Move, MoveOutcome, Polarity, PolarityRule, Rules, Team, Topology,
+    TurnOrder,
+};
+use crate::bot_player::BotPlayer;
+use crate::layout::Layout;
+use crate::notation::{column_name, format_move, row_name};
+use crate::record::{format_date, GameRecord};
 use crate::remote_player::Command;
 use crate::scenes::loading::Textures;
 use crate::scenes::menu::Player;
 use crate::ui::button_trait::ButtonTrait;
 use crate::ui::complex_button::ComplexButton;
 use crate::ui::counter::Counter;
-use crate::{choose_font_size, new_button, AnyError, BASE_FONT_SIZE, STYLE};
+use crate::{
+    choose_font_size, new_button, render_button_flat, AnyError, BASE_FONT_SIZE, FONT, STYLE,
+};
 use juquad::widgets::anchor::Anchor;
 use juquad::widgets::button::Button;
+use juquad::widgets::button_group::ButtonGroup;
 use juquad::widgets::text::TextRect;
 use juquad::widgets::Widget;
-use macroquad::color::{Color, BLACK, DARKGRAY, WHITE};
+use macroquad::color::{Color, DARKGRAY, WHITE};
 use macroquad::input::{is_mouse_button_released, mouse_position, MouseButton};
 use macroquad::math::{IVec2, Rect, Vec2};
+use macroquad::miniquad::date::now;
 use macroquad::prelude::{
-    clear_background, draw_line, draw_rectangle, draw_text, is_key_down, is_key_pressed,
-    is_mouse_button_pressed, measure_text, next_frame, screen_height, screen_width, KeyCode,
-    Texture2D, GRAY,
+    clear_background, draw_line, draw_poly, draw_poly_lines, draw_rectangle, draw_rectangle_lines,
+    draw_text, get_internal_gl, is_key_down, is_key_pressed, is_mouse_button_pressed, measure_text,
+    next_frame, screen_height, screen_width, KeyCode, Texture2D, GRAY,
 };
 use std::sync::mpsc::{Receiver, Sender};
 
@@ -25,36 +36,66 @@ const BOARD_LEFT_COEF: f32 = 0.15;
 const BOARD_WIDTH_COEF: f32 = 1.0 - 2.0 * BOARD_LEFT_COEF;
 const BOARD_HEIGHT_COEF: f32 = 0.58;
 
-const WHITE_HINT: Color = Color::new(1.0, 1.0, 1.0, 0.3);
-const BLACK_HINT: Color = Color::new(0.0, 0.0, 0.0, 0.3);
-const WHITE_FULL: Color = Color::new(1.0, 1.0, 1.0, 0.7);
-const BLACK_FULL: Color = Color::new(0.0, 0.0, 0.0, 0.7);
+/// Alpha of the stones that are not on the board yet.
+const HINT: f32 = 0.3;
+/// Alpha of the stones on the board.
+const FULL: f32 = 0.7;
+const SQRT_3: f32 = 1.732_050_8;
 const TRANSPARENT: Color = Color::new(0.0, 0.0, 0.0, 0.0);
+const RECORD_PATH: &str = "orthomagnet_game.txt";
+const MAP_PATH: &str = "orthomagnet_map.txt";
+const BLOCKED_CELL: Color = Color::new(0.2, 0.2, 0.25, 0.9);
+const WALL: Color = Color::new(0.45, 0.25, 0.1, 1.0);
+const OVERLAY: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+const LAST_MOVE: Color = Color::new(0.9, 0.6, 0.1, 0.8);
+
+/// What the player chose when leaving the game scene.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Exit {
+    Quit,
+    Menu,
+}
 
 pub async fn scene(
     textures: Textures,
     player: Player,
+    rules: Rules,
     from_remote: Option<Receiver<Command>>,
     to_remote: Option<Sender<Command>>,
-) -> Result<(), AnyError> {
+) -> Result<Exit, AnyError> {
     let mut width = screen_width();
     let mut height = screen_height();
     let mut board = Board::new_default_size();
-    let (mut _font_size, mut buttons) = reset(
-        width,
-        height,
-        board.size_rows,
-        board.size_columns,
-        &textures,
-    );
+    board.set_rules(rules);
+    // only used if the rules let the player choose the polarity of each stone
+    let mut polarity = Polarity::Attract;
+    // the tool of the map editor, while editing
+    let mut editor: Option<Tool> = None;
+    let (mut _font_size, mut buttons) = reset(width, height, &board, polarity, editor, &textures);
     let (remote_color, local_color, local_team) = if let Player::Client = player {
-        (WHITE_HINT, BLACK_HINT, Team::Black)
+        (
+            team_color(Team::White, HINT),
+            team_color(Team::Black, HINT),
+            Team::Black,
+        )
     } else {
         // TODO: any way to avoid setting this on Player::Local?
-        (BLACK_HINT, WHITE_HINT, Team::White)
+        (
+            team_color(Team::Black, HINT),
+            team_color(Team::White, HINT),
+            Team::White,
+        )
     };
     let mut remote_mouse = None;
     let mut previous_mouse_tile = None;
+    // the stone being moved, while dragging it once a single cell is left empty
+    let mut dragging: Option<IVec2> = None;
+    let mut reviewing = false;
+    let mut bot = if let Player::Ai(bot_kind) = player {
+        Some(BotPlayer::new(bot_kind.create()))
+    } else {
+        None
+    };
     clear_background(GRAY);
     next_frame().await; // ignore last click
     loop {
@@ -63,29 +104,153 @@ pub async fn scene(
         if new_width != width || new_height != height {
             width = new_width;
             height = new_height;
-            (_font_size, buttons) = reset(
-                width,
-                height,
-                board.size_rows,
-                board.size_columns,
-                &textures,
-            );
+            (_font_size, buttons) = reset(width, height, &board, polarity, editor, &textures);
         }
         if is_key_pressed(KeyCode::Escape) {
             break;
         }
+        let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        let save_clicked =
+            control && is_key_pressed(KeyCode::S) || buttons.save.interact().is_clicked();
+        // loading replaces the board, which only the local peer would see
+        let load_clicked = to_remote.is_none()
+            && (control && is_key_pressed(KeyCode::O) || buttons.load.interact().is_clicked());
+        if save_clicked {
+            if editor.is_some() {
+                if let Err(e) = save_map(&board) {
+                    println!("could not save the map to {}: {}", MAP_PATH, e);
+                }
+            } else if let Err(e) = save_record(&board, player) {
+                println!("could not save the game to {}: {}", RECORD_PATH, e);
+            }
+        }
+        if load_clicked {
+            if editor.is_some() {
+                match load_map() {
+                    Ok(layout) => board.set_layout(layout),
+                    Err(e) => println!("could not load the map from {}: {}", MAP_PATH, e),
+                }
+            } else {
+                match load_record(bot.is_some()) {
+                    Ok(loaded) => board = loaded,
+                    Err(e) => println!("could not load the game from {}: {}", RECORD_PATH, e),
+                }
+            }
+            (_font_size, buttons) = reset(width, height, &board, polarity, editor, &textures);
+        }
+        if control && is_key_pressed(KeyCode::C) {
+            set_clipboard(&board.to_position());
+        }
+        if control && is_key_pressed(KeyCode::V) && to_remote.is_none() {
+            match paste_position(&board) {
+                Ok(pasted) => {
+                    board = pasted;
+                    (_font_size, buttons) =
+                        reset(width, height, &board, polarity, editor, &textures);
+                }
+                Err(e) => println!("could not paste a position: {}", e),
+            }
+        }
         if is_key_pressed(KeyCode::R) || buttons.restart.interact().is_clicked() {
             board.reset();
         }
-        if is_key_pressed(KeyCode::Z) && is_key_down(KeyCode::LeftControl)
-            || buttons.undo.interact().is_clicked()
-        {
+        if is_key_pressed(KeyCode::Z) && control && !shift || buttons.undo.interact().is_clicked() {
             board.pop_history();
+            // undo the bot's moves as well, so that the human can try another move
+            while bot.is_some() && board.turn != local_team && !board.moves().is_empty() {
+                board.pop_history();
+            }
+        }
+        let redo_pressed = is_key_pressed(KeyCode::Y) || is_key_pressed(KeyCode::Z) && shift;
+        // the peer would not see the redone moves
+        let redo_clicked = redo_pressed && control || buttons.redo.interact().is_clicked();
+        if to_remote.is_none() && redo_clicked {
+            board.redo();
+            // the undo took back the bot's moves too
+            while bot.is_some() && board.turn != local_team && board.redo().is_some() {}
         }
         if is_mouse_button_pressed(MouseButton::Right) {
             println!("{}", Vec2::from(mouse_position()));
         }
-        board.maybe_change_size(&mut buttons);
+        maybe_change_size(&mut board, &mut buttons);
+        if let Some(polarity_button) = buttons.polarity.as_mut() {
+            if polarity_button.interact().is_clicked() {
+                polarity = polarity.opposite();
+                (_font_size, buttons) = reset(width, height, &board, polarity, editor, &textures);
+            }
+        }
+        // holding shift places a stone with the opposite polarity
+        let placing_polarity = if shift { polarity.opposite() } else { polarity };
+        // changing the topology resets the board, which only the local peer would see
+        if to_remote.is_none() && buttons.topology.interact().is_clicked() {
+            board.set_topology(board.topology.next());
+            (_font_size, buttons) = reset(width, height, &board, polarity, editor, &textures);
+        }
+        if to_remote.is_none() && buttons.edit_map.interact().is_clicked() {
+            editor = match editor {
+                Some(_) => None,
+                None => Some(Tool::Block),
+            };
+            (_font_size, buttons) = reset(width, height, &board, polarity, editor, &textures);
+        }
+        if let (Some(tool), Some(tool_button)) = (editor.as_mut(), buttons.tool.as_mut()) {
+            if tool_button.interact().is_clicked() {
+                *tool = tool.next(board.rules.turn_order.teams());
+                (_font_size, buttons) = reset(width, height, &board, polarity, editor, &textures);
+            }
+        }
+        let result = board.result();
+        if result.is_none() {
+            reviewing = false;
+        }
+        let show_game_over = result.is_some() && !reviewing && editor.is_none();
+        if show_game_over {
+            // the peer would keep the finished game
+            if to_remote.is_none() && buttons.game_over.rematch.interact().is_clicked() {
+                board.reset();
+            }
+            if buttons.game_over.menu.interact().is_clicked() {
+                return Ok(Exit::Menu);
+            }
+            if buttons.game_over.review.interact().is_clicked() {
+                reviewing = true;
+            }
+        }
+        if result.is_none() && buttons.resign.interact().is_clicked() {
+            let resigning_team = if let Player::Local = player {
+                board.turn
+            } else {
+                local_team
+            };
+            board.resign(resigning_team);
+            if let Some(to_remote) = to_remote.as_ref() {
+                to_remote.send(Command::Resign)?;
+            }
+        }
+        let pass_pressed = is_key_pressed(KeyCode::P) || buttons.pass.interact().is_clicked();
+        let local_turn = matches!(player, Player::Local) || board.turn == local_team;
+        if pass_pressed && local_turn && editor.is_none() && board.play(Move::Pass).is_some() {
+            if let Some(to_remote) = to_remote.as_ref() {
+                to_remote.send(Command::Pass)?;
+            }
+        }
+        let can_swap = local_turn && editor.is_none() && board.is_legal(Move::Swap);
+        if can_swap && buttons.swap.interact().is_clicked() {
+            board.play(Move::Swap);
+        }
+        if to_remote.is_none() {
+            update_variations(
+                &mut board,
+                &mut buttons.variations,
+                bot.is_some(),
+                local_team,
+            );
+            if buttons.variations.moves != board.variations.continuations() {
+                buttons.variations =
+                    VariationPanel::new(width, height, board.variations.continuations());
+            }
+        }
         if buttons.toggle_shadows.interact().is_clicked() {
             // unsafe {
             //     SHADOWS = !SHADOWS;
@@ -99,16 +264,34 @@ pub async fn scene(
             width * BOARD_WIDTH_COEF,
             height * BOARD_HEIGHT_COEF,
         );
-        draw_board_lines(board_rect, board.size_rows, board.size_columns);
+        draw_board_lines(board_rect, Grid::of(&board));
+        draw_layout(&board, board_rect);
+        // undoing, restarting or loading can end the relocation while a stone is held
+        dragging = dragging.filter(|tile| is_draggable(&board, *tile));
         match player {
+            _ if editor.is_some() => update_editor(&mut board, editor.unwrap(), board_rect),
             Player::Local => {
-                let local_color = board.turn.choose(TRANSPARENT, WHITE_HINT, BLACK_HINT);
-                let mouse_pos = Vec2::from(mouse_position());
-                let new_tile = get_tile(board_rect, board.size(), mouse_pos);
-                if let Some(tile) = new_tile {
-                    draw_stone(tile, local_color, board_rect, board.size());
-                    maybe_put_stone(&mut board, tile);
-                }
+                let human_turn = result.is_none();
+                update_human(
+                    &mut board,
+                    &mut dragging,
+                    human_turn,
+                    board_rect,
+                    placing_polarity,
+                );
+            }
+            Player::Ai(_) => {
+                let human_turn = result.is_none() && board.turn == local_team;
+                update_human(
+                    &mut board,
+                    &mut dragging,
+                    human_turn,
+                    board_rect,
+                    placing_polarity,
+                );
+                bot.as_mut()
+                    .unwrap()
+                    .update(&mut board, local_team.toggle())?;
             }
             Player::Server | Player::Client => {
                 update_mouses(
@@ -124,52 +307,315 @@ pub async fn scene(
                 )?;
             }
         }
-        draw_stones(&board.board, board_rect, board.size());
-        draw_score(board_rect, &board);
+        draw_stones(&board.board, board_rect, Grid::of(&board));
+        if let Some(outcome) = board.variations.last_outcome() {
+            draw_last_move(outcome, board_rect, Grid::of(&board));
+        }
+        if dragging.is_some() {
+            let color = team_color(board.turn, FULL);
+            draw_dragged_stone(Vec2::from(mouse_position()), color, board_rect, &board);
+        }
+        let thinking = bot.as_ref().is_some_and(|bot| bot.is_thinking());
+        draw_score(board_rect, &board, thinking);
         draw_instructions(&buttons);
+        let local_turn = matches!(player, Player::Local) || board.turn == local_team;
+        if local_turn && editor.is_none() && board.is_legal(Move::Swap) {
+            render_button_flat(&buttons.swap, &STYLE);
+        }
         draw_size(&buttons);
+        if to_remote.is_none() {
+            draw_variations(&buttons.variations);
+            draw_editor_buttons(&buttons);
+        }
+        if show_game_over {
+            draw_game_over(board_rect, &board, result.unwrap(), &buttons.game_over);
+        }
         next_frame().await
     }
-    Ok(())
+    Ok(Exit::Quit)
 }
 
-fn maybe_put_stone(board: &mut Board, tile: IVec2) {
+fn update_variations(
+    board: &mut Board,
+    panel: &mut VariationPanel,
+    against_bot: bool,
+    local_team: Team,
+) {
+    for (button, mv) in panel.continuations.iter_mut().zip(&panel.moves) {
+        if button.interact().is_clicked() {
+            board.play(*mv);
+        }
+    }
+    if panel.promote.interact().is_clicked() {
+        board.variations.promote();
+    }
+    if panel.delete.interact().is_clicked() {
+        board.delete_variation();
+        if against_bot && board.turn != local_team {
+            // like undo, go back to a position where the human is in turn
+            board.pop_history();
+        }
+    }
+}
+
+fn maybe_put_stone(board: &mut Board, tile: IVec2, polarity: Polarity) -> bool {
     if is_mouse_button_released(MouseButton::Left) {
-        board.board_history.push(board.board.clone());
-        let previous_turn = board.turn;
-        try_put_stone(&mut board.turn, &mut board.board, tile);
-        if board.turn == previous_turn {
-            board.board_history.pop();
+        put_stone(board, tile, polarity)
+    } else {
+        false
+    }
+}
+
+/// Shows where the stone of the team in turn would go and what it would pull, and plays it on
+/// click, or drags a stone during the relocation. Does nothing unless `human_turn`.
+fn update_human(
+    board: &mut Board,
+    dragging: &mut Option<IVec2>,
+    human_turn: bool,
+    board_rect: Rect,
+    polarity: Polarity,
+) {
+    if !human_turn {
+        return;
+    }
+    let color = team_color(board.turn, HINT);
+    let mouse_pos = Vec2::from(mouse_position());
+    let new_tile = get_tile(board_rect, Grid::of(board), mouse_pos);
+    if board.is_relocating() {
+        drag_stone(board, dragging, new_tile, color, board_rect);
+    } else if let Some(tile) = new_tile {
+        draw_stone(tile, color, board_rect, Grid::of(board));
+        let mv = board.place_move(to_coord(tile), polarity);
+        draw_pull_preview(board, mv, color, board_rect);
+        maybe_put_stone(board, tile, polarity);
+    }
+}
+
+/// Once a single cell is left empty, stones are moved by dragging one of the player's own stones
+/// from its tile and dropping it on a legal destination.
+fn drag_stone(
+    board: &mut Board,
+    dragging: &mut Option<IVec2>,
+    tile: Option<IVec2>,
+    color: Color,
+    board_rect: Rect,
+) {
+    let grid = Grid::of(board);
+    if is_mouse_button_pressed(MouseButton::Left) {
+        *dragging = tile.filter(|tile| is_draggable(board, *tile));
+    }
+    let Some(from) = *dragging else {
+        return;
+    };
+    outline_tile(from, board_rect, grid, 4.0, color);
+    for mv in board.legal_moves() {
+        if let Move::Relocate { from: lifted, to } = mv {
+            if lifted == to_coord(from) {
+                outline_tile(to_tile(to), board_rect, grid, 2.0, color);
+            }
+        }
+    }
+    if let Some(tile) = tile {
+        let mv = Move::Relocate {
+            from: to_coord(from),
+            to: to_coord(tile),
+        };
+        draw_pull_preview(board, mv, color, board_rect);
+        if is_mouse_button_released(MouseButton::Left) {
+            board.play(mv);
         }
     }
+    if is_mouse_button_released(MouseButton::Left) {
+        *dragging = None;
+    }
+}
+
+/// Whether the tile has a stone of the team in turn, and the game is at the relocation phase.
+fn is_draggable(board: &Board, tile: IVec2) -> bool {
+    let coord = to_coord(tile);
+    board.is_relocating()
+        && !board.is_terminal()
+        && board.contains(coord)
+        && board.get_team(coord) == board.turn
+}
+
+/// Returns whether the stone was placed. The polarity is ignored unless the rules let the
+/// player choose it.
+fn put_stone(board: &mut Board, tile: IVec2, polarity: Polarity) -> bool {
+    board
+        .play(board.place_move(to_coord(tile), polarity))
+        .is_some()
+}
+
+fn save_record(board: &Board, player: Player) -> Result<(), AnyError> {
+    // in local games, every team is named after its colour
+    let players = match player {
+        Player::Local => Vec::new(),
+        Player::Ai(bot_kind) => vec![
+            (Team::White, "Human".to_string()),
+            (Team::Black, format!("Computer ({})", bot_kind.name())),
+        ],
+        Player::Server | Player::Client => vec![
+            (Team::White, "Server".to_string()),
+            (Team::Black, "Client".to_string()),
+        ],
+    };
+    let players = players
+        .iter()
+        .map(|(team, name)| (*team, name.as_str()))
+        .collect::<Vec<_>>();
+    let record = GameRecord::from_board(board, &players, &format_date(now()));
+    record.save(RECORD_PATH)
+}
+
+/// The bots only play white against black, so against them other turn orders are rejected.
+fn load_record(against_bot: bool) -> Result<Board, AnyError> {
+    let board = GameRecord::load(RECORD_PATH)?.replay()?;
+    if against_bot && board.rules.turn_order != TurnOrder::default() {
+        return Err(format!(
+            "the computer only plays white against black, not {}",
+            board.rules.turn_order.name()
+        )
+        .into());
+    }
+    Ok(board)
+}
+
+fn save_map(board: &Board) -> Result<(), AnyError> {
+    std::fs::write(MAP_PATH, board.layout.to_text())?;
+    Ok(())
+}
+
+fn load_map() -> Result<Layout, AnyError> {
+    let text = std::fs::read_to_string(MAP_PATH)?;
+    Ok(Layout::from_text(&text)?)
+}
+
+/// Keeps the topology and rules of the current board.
+fn paste_position(board: &Board) -> Result<Board, AnyError> {
+    let text = get_clipboard().ok_or("the clipboard is empty")?;
+    let mut pasted = Board::from_position(&text)?;
+    pasted.topology = board.topology;
+    pasted.rules = board.rules;
+    let teams = board.rules.turn_order.teams();
+    if !teams.contains(&pasted.turn) {
+        return Err(format!(
+            "it's the turn of {}, who is not playing this game",
+            pasted.turn.name()
+        )
+        .into());
+    }
+    let playing = |team: Team| team == Team::Empty || teams.contains(&team);
+    if let Some(team) = pasted.board.iter().flatten().find(|team| !playing(**team)) {
+        return Err(format!(
+            "there are stones of {}, who is not playing this game",
+            team.name()
+        )
+        .into());
+    }
+    Ok(pasted)
+}
+
+fn set_clipboard(text: &str) {
+    unsafe { get_internal_gl().quad_context.clipboard_set(text) }
+}
+
+fn get_clipboard() -> Option<String> {
+    unsafe { get_internal_gl().quad_context.clipboard_get() }
+}
+
+fn to_coord(tile: IVec2) -> Coord {
+    Coord::new(tile.x, tile.y)
+}
+
+fn to_tile(coord: Coord) -> IVec2 {
+    IVec2::new(coord.x, coord.y)
+}
+
+fn maybe_change_size(
+    board: &mut Board,
+    buttons: &mut Buttons, // TODO: extract to Actions to extract side-effects?
+) {
+    let (mut size_rows, mut size_columns) = board.size();
+    let mut changed_rows = false;
+    let mut changed_columns = false;
+    if buttons.rows.increase.interact().is_clicked() {
+        size_rows += 1;
+        changed_rows = true;
+    }
+    if buttons.rows.decrease.interact().is_clicked() {
+        size_rows -= 1;
+        changed_rows = true;
+    }
+    if buttons.columns.increase.interact().is_clicked() {
+        size_columns += 1;
+        changed_columns = true;
+    }
+    if buttons.columns.decrease.interact().is_clicked() {
+        size_columns -= 1;
+        changed_columns = true;
+    }
+    if changed_rows {
+        buttons.rows.update(size_rows);
+    }
+    if changed_columns {
+        buttons.columns.update(size_columns);
+    }
+    if changed_rows || changed_columns {
+        board.resize(size_rows, size_columns);
+    }
 }
 
 fn reset(
     width: f32,
     height: f32,
-    row_count: i32,
-    column_count: i32,
+    board: &Board,
+    polarity: Polarity,
+    editor: Option<Tool>,
     textures: &Textures,
 ) -> (f32, Buttons) {
     let font_size = choose_font_size(width, height) * 2.0;
-    let buttons = Buttons::new(width, height, row_count, column_count, textures);
+    let buttons = Buttons::new(width, height, board, polarity, editor, textures);
     (font_size, buttons)
 }
 
 pub struct Buttons {
     pub restart: ComplexButton,
     pub undo: ComplexButton,
+    pub redo: ComplexButton,
     pub rows: Counter,
     pub columns: Counter,
+    pub resign: Button,
+    pub pass: Button,
+    /// Only drawn when the pie rule lets the player take over the first stone.
+    pub swap: Button,
+    pub save: Button,
+    pub load: Button,
+    pub topology: Button,
+    /// Only present if the rules let the player choose the polarity of each stone.
+    pub polarity: Option<Button>,
+    /// Switches between playing and editing the map.
+    pub edit_map: Button,
+    /// Only present while editing the map.
+    pub tool: Option<Button>,
     pub toggle_shadows: Button,
+    pub game_over: GameOverButtons,
+    pub variations: VariationPanel,
+}
+
+pub struct GameOverButtons {
+    pub rematch: Button,
+    pub menu: Button,
+    pub review: Button,
 }
 
 impl Buttons {
     pub fn new(
         screen_width: f32,
         screen_height: f32,
-        row_count: i32,
-        column_count: i32,
+        board: &Board,
+        polarity: Polarity,
+        editor: Option<Tool>,
         textures: &Textures,
     ) -> Self {
         let mut font_size = choose_font_size(screen_width, screen_height);
@@ -191,27 +637,189 @@ impl Buttons {
             ComplexButton::new(anchor, vec![texture], texture_size, text, font_size)
         };
         let undo = new_complex_button(undo_anchor, textures.undo, "Undo");
+        let redo_anchor = Anchor::bottom_left(undo.rect().right() + left_pad, bottom);
+        let redo = new_complex_button(redo_anchor, textures.undo, "Redo").with_flipped_icon();
 
         let restart_anchor = Anchor::bottom_left(undo.rect().x, undo.rect().y - undo.rect().h);
         let restart = new_complex_button(restart_anchor, textures.restart, "Restart");
 
         let anchor_columns =
             Anchor::bottom_right(((1.0 - BOARD_LEFT_COEF) * screen_width).round(), bottom);
-        let columns = Counter::new(column_count, anchor_columns, counter_inner_pad, font_size);
+        let columns = Counter::new(
+            board.size_columns,
+            anchor_columns,
+            counter_inner_pad,
+            font_size,
+        );
         let anchor_rows = Anchor::top_right(columns.rect().x - left_pad * 0.5, columns.rect().y);
-        let rows = Counter::new(row_count, anchor_rows, counter_inner_pad, font_size);
+        let rows = Counter::new(board.size_rows, anchor_rows, counter_inner_pad, font_size);
+
+        let resign_anchor = Anchor::bottom_left(redo.rect().right() + left_pad, bottom);
+        let resign = new_button("Resign", resign_anchor, font_size);
+        let pass_anchor = Anchor::bottom_left(resign.rect().right() + left_pad, bottom);
+        let pass = new_button("Pass", pass_anchor, font_size);
+        let swap_anchor = Anchor::bottom_left(pass.rect().right() + left_pad, bottom);
+        let swap = new_button("Swap", swap_anchor, font_size);
+        let save_anchor =
+            Anchor::bottom_left(restart.rect().right() + left_pad, restart.rect().bottom());
+        let (save_text, load_text, edit_map_text) = match editor {
+            Some(_) => ("Save map", "Load map", "Play"),
+            None => ("Save", "Load", "Edit map"),
+        };
+        let save = new_button(save_text, save_anchor, font_size);
+        let load_anchor = Anchor::bottom_left(save.rect().right() + left_pad, save.rect().bottom());
+        let load = new_button(load_text, load_anchor, font_size);
+        let edit_map_anchor =
+            Anchor::bottom_left(load.rect().right() + left_pad, load.rect().bottom());
+        let edit_map = new_button(edit_map_text, edit_map_anchor, font_size);
+
+        let topology = new_button(
+            &topology_text(board.topology),
+            topology_anchor(screen_width),
+            font_size,
+        );
+        let polarity = (board.rules.polarity == PolarityRule::PlayerChoice).then(|| {
+            let anchor = Anchor::top_center(topology.rect().center().x, topology.rect().bottom());
+            new_button(&polarity_text(polarity), anchor, font_size)
+        });
+        let tool = editor.map(|tool| {
+            let above = polarity.as_ref().unwrap_or(&topology).rect();
+            let anchor = Anchor::top_center(above.center().x, above.bottom());
+            new_button(&format!("Paint: {}", tool.name()), anchor, font_size)
+        });
 
         let anchor = Anchor::top_left(0.0, 0.0);
         let toggle_shadows = new_button("toggle shadows", anchor, font_size);
+
+        let game_over_anchor = Anchor::top_center(
+            screen_width * 0.5,
+            screen_height * (BOARD_TOP_COEF + BOARD_HEIGHT_COEF * 0.5),
+        );
+        let button_group = ButtonGroup::new_with_font(font_size, unsafe { FONT }, game_over_anchor);
+        let [rematch, menu, review] = button_group.create(["Rematch", "Menu", "Review"]);
         Self {
             restart,
             undo,
+            redo,
             rows,
             columns,
+            resign,
+            pass,
+            swap,
+            save,
+            load,
+            topology,
+            polarity,
+            edit_map,
+            tool,
             toggle_shadows,
+            game_over: GameOverButtons {
+                rematch,
+                menu,
+                review,
+            },
+            variations: VariationPanel::new(
+                screen_width,
+                screen_height,
+                board.variations.continuations(),
+            ),
         }
     }
 }
+
+/// Buttons to the right of the board to switch between the moves tried from the current
+/// position, and to promote or delete the line that leads to it.
+pub struct VariationPanel {
+    pub title: TextRect,
+    pub promote: Button,
+    pub delete: Button,
+    pub moves: Vec<Move>,
+    /// One button per move in `moves`, the main line first.
+    pub continuations: Vec<Button>,
+}
+
+impl VariationPanel {
+    pub fn new(screen_width: f32, screen_height: f32, moves: Vec<Move>) -> Self {
+        let font_size = choose_font_size(screen_width, screen_height) * 1.2;
+        let pad = 8.0;
+        let left = ((1.0 - BOARD_LEFT_COEF) * screen_width + pad).round();
+        let top = (BOARD_TOP_COEF * screen_height).round();
+        let title = TextRect::new("Variations", Anchor::top_left(left, top), font_size);
+        let promote_anchor = Anchor::top_left(left, title.rect.bottom() + pad);
+        let promote = new_button("Promote", promote_anchor, font_size);
+        let delete_anchor = Anchor::top_left(left, promote.rect().bottom() + pad);
+        let delete = new_button("Delete", delete_anchor, font_size);
+        let mut continuations: Vec<Button> = Vec::new();
+        for (i, mv) in moves.iter().enumerate() {
+            let y = match continuations.last() {
+                Some(previous) => previous.rect().bottom(),
+                None => delete.rect().bottom() + pad * 2.0,
+            };
+            let text = if i == 0 {
+                format!("{} (main)", format_move(*mv))
+            } else {
+                format_move(*mv)
+            };
+            continuations.push(new_button(&text, Anchor::top_left(left, y), font_size));
+        }
+        Self {
+            title,
+            promote,
+            delete,
+            moves,
+            continuations,
+        }
+    }
+}
+/// What clicking on the board paints while editing the map.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Tool {
+    Block,
+    Wall,
+    Stone(Team),
+}
+impl Tool {
+    pub fn name(&self) -> String {
+        match self {
+            Tool::Block => "blocked cells".to_string(),
+            Tool::Wall => "walls".to_string(),
+            Tool::Stone(team) => format!("{} stones", team.name()),
+        }
+    }
+    /// Cycles through blocking, walls and the stones of each playing team.
+    #[must_use]
+    pub fn next(&self, teams: &[Team]) -> Tool {
+        match self {
+            Tool::Block => Tool::Wall,
+            Tool::Wall => Tool::Stone(teams[0]),
+            Tool::Stone(team) => match teams.iter().position(|t| t == team) {
+                Some(index) if index + 1 < teams.len() => Tool::Stone(teams[index + 1]),
+                _ => Tool::Block,
+            },
+        }
+    }
+}
+
+fn topology_text(topology: Topology) -> String {
+    format!("Topology: {}", topology.name())
+}
+
+fn polarity_text(polarity: Polarity) -> String {
+    let name = |polarity| match polarity {
+        Polarity::Attract => "attract",
+        Polarity::Repel => "repel",
+    };
+    format!(
+        "Stones {} (shift: {})",
+        name(polarity),
+        name(polarity.opposite())
+    )
+}
+
+fn topology_anchor(screen_width: f32) -> Anchor {
+    Anchor::top_center(screen_width * 0.5, 0.0)
+}
+
 fn update_mouses(
     board: &mut Board,
     remote_mouse: &mut Option<IVec2>,
@@ -223,16 +831,21 @@ fn update_mouses(
     to_remote: &Sender<Command>,
     from_remote: &Receiver<Command>,
 ) -> Result<(), AnyError> {
-    update_remote_mouse(remote_mouse, from_remote);
+    update_remote_mouse(board, local_team, remote_mouse, from_remote);
     if let Some(tile) = remote_mouse.as_ref() {
-        draw_stone(*tile, remote_color, board_rect, board.size());
+        draw_stone(*tile, remote_color, board_rect, Grid::of(board));
     }
-    let new_tile_opt = update_local_mouse(board, local_team, board_rect, local_color);
+    let new_tile_opt = update_local_mouse(board, local_team, board_rect, local_color, to_remote)?;
     send_local_mouse_update(previous_mouse_tile, to_remote, new_tile_opt)?;
     Ok(())
 }
 
-fn update_remote_mouse(remote_mouse: &mut Option<IVec2>, from_remote: &Receiver<Command>) {
+fn update_remote_mouse(
+    board: &mut Board,
+    local_team: Team,
+    remote_mouse: &mut Option<IVec2>,
+    from_remote: &Receiver<Command>,
+) {
     while let Ok(command) = from_remote.try_recv() {
         match command {
             Command::StoneHover { x, y } => {
@@ -241,6 +854,24 @@ fn update_remote_mouse(remote_mouse: &mut Option<IVec2>, from_remote: &Receiver<
             Command::StopStoneHover => {
                 *remote_mouse = None;
             }
+            Command::PlaceStone { x, y } => {
+                if board.turn == local_team {
+                    println!(
+                        "ignoring stone placed by remote out of turn at ({}, {})",
+                        x, y
+                    );
+                } else if !put_stone(board, IVec2::new(x, y), Polarity::Attract) {
+                    println!("ignoring illegal stone placed by remote at ({}, {})", x, y);
+                }
+            }
+            Command::Pass => {
+                if board.turn == local_team {
+                    println!("ignoring pass by remote out of turn");
+                } else if board.play(Move::Pass).is_none() {
+                    println!("ignoring illegal pass by remote");
+                }
+            }
+            Command::Resign => board.resign(local_team.toggle()),
             Command::Connected => unreachable!(),
         }
     }
@@ -251,16 +882,20 @@ fn update_local_mouse(
     local_team: Team,
     board_rect: Rect,
     local_color: Color,
-) -> Option<IVec2> {
+    to_remote: &Sender<Command>,
+) -> Result<Option<IVec2>, AnyError> {
     let mouse_pos = Vec2::from(mouse_position());
-    let new_tile = get_tile(board_rect, board.size(), mouse_pos);
+    let new_tile = get_tile(board_rect, Grid::of(board), mouse_pos);
     if let Some(tile) = new_tile {
-        draw_stone(tile, local_color, board_rect, board.size());
-        if board.turn == local_team {
-            maybe_put_stone(board, tile);
+        draw_stone(tile, local_color, board_rect, Grid::of(board));
+        if board.turn == local_team && maybe_put_stone(board, tile, Polarity::Attract) {
+            to_remote.send(Command::PlaceStone {
+                x: tile.x,
+                y: tile.y,
+            })?;
         }
     }
-    new_tile
+    Ok(new_tile)
 }
 
 fn send_local_mouse_update(
@@ -283,157 +918,393 @@ fn send_local_mouse_update(
     Ok(())
 }
 
-fn try_put_stone(turn: &mut Team, mut board: &mut Vec<Vec<Team>>, tile: IVec2) {
-    let row_count = board[0].len() as i32;
-    let column_count = board.len() as i32;
-    let clicked = &mut board[tile.x as usize][tile.y as usize];
-    if let Team::Empty = clicked {
-        *clicked = *turn;
-        let side_xp = tile + IVec2::new(1, 0);
-        let side_xn = tile + IVec2::new(-1, 0);
-        let side_yp = tile + IVec2::new(0, 1);
-        let side_yn = tile + IVec2::new(0, -1);
-        let mut check_xp = Team::Empty == get_team(&board, side_xp);
-        let mut check_xn = Team::Empty == get_team(&board, side_xn);
-        let mut check_yp = Team::Empty == get_team(&board, side_yp);
-        let mut check_yn = Team::Empty == get_team(&board, side_yn);
-        // space for pulling stones in +x
-        let opponent = turn.toggle();
-        let mut check_dir = |adjacent_to_new_stone: IVec2,
-                             diff_new_stone_with_pulled_stone: IVec2,
-                             keep_checking: &mut bool| {
-            check_direction(
-                turn,
-                opponent,
-                tile,
-                adjacent_to_new_stone,
-                diff_new_stone_with_pulled_stone,
-                &mut board,
-                keep_checking,
-            );
-        };
-        for i in 2..row_count {
-            check_dir(side_xp, IVec2::new(i, 0), &mut check_xp);
-            check_dir(side_xn, IVec2::new(-i, 0), &mut check_xn);
-        }
-        for i in 2..column_count {
-            check_dir(side_yp, IVec2::new(0, i), &mut check_yp);
-            check_dir(side_yn, IVec2::new(0, -i), &mut check_yn);
+/// The size of the board and the shape of its cells, to know where each tile is drawn.
+#[derive(Copy, Clone, Debug)]
+struct Grid {
+    size_rows: i32,
+    size_columns: i32,
+    hexagonal: bool,
+}
+impl Grid {
+    fn of(board: &Board) -> Self {
+        Self {
+            size_rows: board.size_rows,
+            size_columns: board.size_columns,
+            hexagonal: board.topology.is_hexagonal(),
         }
-        *turn = turn.toggle();
+    }
+    fn tiles(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.size_columns).flat_map(move |x| (0..self.size_rows).map(move |y| IVec2::new(x, y)))
     }
 }
 
-fn check_direction(
-    new_stone_color: &mut Team,
-    opponent: Team,
-    new_stone: IVec2,
-    adjacent_to_new_stone: IVec2,
-    diff_new_stone_with_pulled_stone: IVec2,
-    mut board: &mut &mut Vec<Vec<Team>>,
-    keep_checking: &mut bool,
-) {
-    if *keep_checking {
-        let pulled_stone = new_stone + diff_new_stone_with_pulled_stone;
-        let pulled_stone_color = get_team(&board, pulled_stone);
-        if *new_stone_color == pulled_stone_color {
-            *keep_checking = false;
+fn draw_board_lines(rect: Rect, grid: Grid) {
+    if grid.hexagonal {
+        for tile in grid.tiles() {
+            outline_tile(tile, rect, grid, 2.0, DARKGRAY);
         }
-        if opponent == pulled_stone_color {
-            *get_team_mut(&mut board, adjacent_to_new_stone) = *new_stone_color;
-            *get_team_mut(&mut board, pulled_stone) = Team::Empty;
-            *keep_checking = false;
+    } else {
+        let Rect { x, y, w, h } = rect;
+        let dx = w / grid.size_columns as f32;
+        let dy = h / grid.size_rows as f32;
+        for vertical_line_i in 0..grid.size_columns + 1 {
+            let dx_i = vertical_line_i as f32 * dx;
+            draw_line(x + dx_i, y, x + dx_i, y + h, 2.0, DARKGRAY);
+        }
+
+        for horizontal_line_i in 0..grid.size_rows + 1 {
+            let dy_i = horizontal_line_i as f32 * dy;
+            draw_line(x, y + dy_i, x + w, y + dy_i, 2.0, DARKGRAY);
         }
     }
+    draw_coordinate_labels(rect, grid);
 }
 
-fn draw_board_lines(rect: Rect, size_rows: i32, size_columns: i32) {
-    let Rect { x, y, w, h } = rect;
-    let dx = w / size_columns as f32;
-    let dy = h / size_rows as f32;
-    for vertical_line_i in 0..size_columns + 1 {
-        let dx_i = vertical_line_i as f32 * dx;
-        draw_line(x + dx_i, y, x + dx_i, y + h, 2.0, DARKGRAY);
+/// Column letters below the board and row numbers to its left, as used by the move notation.
+fn draw_coordinate_labels(rect: Rect, grid: Grid) {
+    let font_size = choose_font_size(screen_width(), screen_height());
+    let pad = font_size * 0.5;
+    for column in 0..grid.size_columns {
+        let label = column_name(column);
+        let dimensions = measure_text(&label, None, font_size as u16, 1.0);
+        let tile = tile_rect(IVec2::new(column, grid.size_rows - 1), rect, grid);
+        let label_x = tile.center().x - dimensions.width * 0.5;
+        let label_y = tile.bottom() + pad + dimensions.offset_y;
+        draw_text(&label, label_x, label_y, font_size, DARKGRAY);
     }
-
-    for horizontal_line_i in 0..size_rows + 1 {
-        let dy_i = horizontal_line_i as f32 * dy;
-        draw_line(x, y + dy_i, x + w, y + dy_i, 2.0, DARKGRAY);
+    for row in 0..grid.size_rows {
+        let label = row_name(row);
+        let dimensions = measure_text(&label, None, font_size as u16, 1.0);
+        let tile = tile_rect(IVec2::new(0, row), rect, grid);
+        let label_x = tile.x - pad - dimensions.width;
+        let label_y = tile.center().y + dimensions.height * 0.5;
+        draw_text(&label, label_x, label_y, font_size, DARKGRAY);
     }
 }
 
-fn get_tile(board_rect: Rect, (row_count, column_count): (i32, i32), pos: Vec2) -> Option<IVec2> {
-    if board_rect.contains(pos) {
-        let tile_size_x = board_rect.w / column_count as f32;
-        let tile_size_y = board_rect.h / row_count as f32;
-        let x_i = ((pos.x - board_rect.x) / tile_size_x) as i32;
-        let y_i = ((pos.y - board_rect.y) / tile_size_y) as i32;
-        Some(IVec2::new(x_i, y_i))
-    } else {
+/// Returns the tile under `pos`, with x as the column and y as the row, like [`Coord`].
+fn get_tile(board_rect: Rect, grid: Grid, pos: Vec2) -> Option<IVec2> {
+    if !board_rect.contains(pos) {
         None
+    } else if grid.hexagonal {
+        let (radius, first_center) = hex_layout(board_rect, grid);
+        let relative = pos - first_center;
+        let row = relative.y / (1.5 * radius);
+        let column = relative.x / (SQRT_3 * radius) - row * 0.5;
+        let tile = round_hex(column, row);
+        let inside =
+            (0..grid.size_columns).contains(&tile.x) && (0..grid.size_rows).contains(&tile.y);
+        inside.then_some(tile)
+    } else {
+        let tile_width = board_rect.w / grid.size_columns as f32;
+        let tile_height = board_rect.h / grid.size_rows as f32;
+        let column = ((pos.x - board_rect.x) / tile_width) as i32;
+        let row = ((pos.y - board_rect.y) / tile_height) as i32;
+        Some(IVec2::new(column, row))
     }
 }
-fn get_team(board: &Vec<Vec<Team>>, tile: IVec2) -> Team {
-    let row_count = board[0].len() as i32;
-    let column_count = board.len() as i32;
-    let x = ((tile.x + column_count) % column_count) as usize;
-    let y = ((tile.y + row_count) % row_count) as usize;
-    board[x][y]
+
+/// Rounds fractional hex coordinates to the hexagon that contains them, by rounding the 3 cube
+/// coordinates and recomputing the one that moved the most, so that they still add up to 0.
+fn round_hex(column: f32, row: f32) -> IVec2 {
+    let third = -column - row;
+    let (mut rounded_column, rounded_third, mut rounded_row) =
+        (column.round(), third.round(), row.round());
+    let column_diff = (rounded_column - column).abs();
+    let third_diff = (rounded_third - third).abs();
+    let row_diff = (rounded_row - row).abs();
+    if column_diff > third_diff && column_diff > row_diff {
+        rounded_column = -rounded_third - rounded_row;
+    } else if row_diff > third_diff {
+        rounded_row = -rounded_column - rounded_third;
+    }
+    IVec2::new(rounded_column as i32, rounded_row as i32)
 }
 
-fn get_team_mut(board: &mut Vec<Vec<Team>>, tile: IVec2) -> &mut Team {
-    let row_count = board[0].len() as i32;
-    let column_count = board.len() as i32;
-    let x = ((tile.x + column_count) % column_count) as usize;
-    let y = ((tile.y + row_count) % row_count) as usize;
-    board.get_mut(x).unwrap().get_mut(y).unwrap()
+/// The radius of the hexagons and the center of the top left one, so that the rhombus of
+/// hexagons is as big as possible and centered in the board rect.
+fn hex_layout(board_rect: Rect, grid: Grid) -> (f32, Vec2) {
+    let rows = grid.size_rows as f32;
+    let width_in_radii = SQRT_3 * (grid.size_columns as f32 + (rows - 1.0) * 0.5);
+    let height_in_radii = 1.5 * (rows - 1.0) + 2.0;
+    let radius = (board_rect.w / width_in_radii).min(board_rect.h / height_in_radii);
+    let first_center = Vec2::new(
+        board_rect.x + (board_rect.w - radius * width_in_radii + radius * SQRT_3) * 0.5,
+        board_rect.y + (board_rect.h - radius * height_in_radii) * 0.5 + radius,
+    );
+    (radius, first_center)
 }
 
-fn draw_stones(board: &Vec<Vec<Team>>, board_rect: Rect, board_size: (i32, i32)) {
+fn draw_stones(board: &[Vec<Team>], board_rect: Rect, grid: Grid) {
     for (x_i, column) in board.iter().enumerate() {
         for (y_i, team) in column.iter().enumerate() {
-            let color = team.choose(TRANSPARENT, WHITE_FULL, BLACK_FULL);
-            draw_stone(
-                IVec2::new(x_i as i32, y_i as i32),
-                color,
-                board_rect,
-                board_size,
-            );
+            let color = team_color(*team, FULL);
+            draw_stone(IVec2::new(x_i as i32, y_i as i32), color, board_rect, grid);
         }
     }
 }
-fn draw_stone(tile: IVec2, color: Color, board_rect: Rect, (size_rows, size_columns): (i32, i32)) {
-    let tile_size_x = board_rect.w / size_columns as f32;
-    let tile_size_y = board_rect.h / size_rows as f32;
-    draw_rectangle(
-        board_rect.x + tile.x as f32 * tile_size_x,
-        board_rect.y + tile.y as f32 * tile_size_y,
-        tile_size_x,
-        tile_size_y,
-        color,
-    );
+fn draw_stone(tile: IVec2, color: Color, board_rect: Rect, grid: Grid) {
+    let rect = tile_rect(tile, board_rect, grid);
+    if grid.hexagonal {
+        let center = rect.center();
+        draw_poly(center.x, center.y, 6, rect.h * 0.5, 90.0, color);
+    } else {
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+    }
 }
-fn draw_score(board_rect: Rect, board: &Board) {
-    let (whites, blacks) = board.score();
-    let white_str = format!("{}", whites);
+fn outline_tile(tile: IVec2, board_rect: Rect, grid: Grid, thickness: f32, color: Color) {
+    let rect = tile_rect(tile, board_rect, grid);
+    if grid.hexagonal {
+        let center = rect.center();
+        draw_poly_lines(center.x, center.y, 6, rect.h * 0.5, 90.0, thickness, color);
+    } else {
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, thickness, color);
+    }
+}
+
+/// Draws the blocked cells and the walls of the map.
+fn draw_layout(board: &Board, board_rect: Rect) {
+    let grid = Grid::of(board);
+    for tile in grid.tiles() {
+        if board.layout.is_blocked(to_coord(tile)) {
+            draw_stone(tile, BLOCKED_CELL, board_rect, grid);
+        }
+    }
+    for wall in board.layout.walls() {
+        let (a, b) = wall.cells();
+        // a wall across a wrapping edge is drawn on both sides of the board
+        for (from, to) in [(a, b), (b, a)] {
+            for direction in board.edge_directions() {
+                if board.neighbour(from, direction) == Some(to) {
+                    draw_wall(to_tile(from), direction, board_rect, grid, WALL);
+                }
+            }
+        }
+    }
+}
+
+/// Draws a line over the edge between the tile and its neighbour in that direction.
+fn draw_wall(tile: IVec2, direction: Coord, board_rect: Rect, grid: Grid, color: Color) {
+    let rect = tile_rect(tile, board_rect, grid);
+    let center = rect.center();
+    let neighbour_center = tile_rect(tile + to_tile(direction), board_rect, grid).center();
+    let middle = (center + neighbour_center) * 0.5;
+    let along = (neighbour_center - center).normalize().perp();
+    let half_length = if grid.hexagonal {
+        rect.h * 0.25
+    } else if direction.x != 0 {
+        rect.h * 0.5
+    } else {
+        rect.w * 0.5
+    };
+    let start = middle - along * half_length;
+    let end = middle + along * half_length;
+    draw_line(start.x, start.y, end.x, end.y, 6.0, color);
+}
+
+/// Previews what the tool would paint under the mouse, and paints it when clicking.
+fn update_editor(board: &mut Board, tool: Tool, board_rect: Rect) {
+    let grid = Grid::of(board);
+    let mouse_pos = Vec2::from(mouse_position());
+    let Some(tile) = get_tile(board_rect, grid, mouse_pos) else {
+        return;
+    };
+    let coord = to_coord(tile);
+    let clicked = is_mouse_button_released(MouseButton::Left);
+    match tool {
+        Tool::Block => {
+            draw_stone(tile, BLOCKED_CELL, board_rect, grid);
+            if clicked {
+                board.edit_layout(|layout| layout.toggle_blocked(coord));
+            }
+        }
+        Tool::Stone(team) => {
+            draw_stone(tile, team_color(team, HINT), board_rect, grid);
+            if clicked {
+                board.edit_layout(|layout| layout.toggle_stone(coord, team));
+            }
+        }
+        Tool::Wall => {
+            // the wall goes on the edge whose neighbour is closest to the mouse
+            let distance = |direction: &Coord| {
+                let neighbour = tile + to_tile(*direction);
+                tile_rect(neighbour, board_rect, grid)
+                    .center()
+                    .distance(mouse_pos)
+            };
+            let directions = board.edge_directions().into_iter();
+            let direction = directions
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+                .unwrap();
+            let Some(neighbour) = board.neighbour(coord, direction) else {
+                return;
+            };
+            draw_wall(tile, direction, board_rect, grid, LAST_MOVE);
+            if clicked {
+                board.edit_layout(|layout| layout.toggle_wall(coord, neighbour));
+            }
+        }
+    }
+}
+
+/// Outlines the stone placed and the stones pulled in the last move, and shows faintly where
+/// the pulled stones were.
+fn draw_last_move(outcome: &MoveOutcome, board_rect: Rect, grid: Grid) {
+    let mut outlined = outcome.placed.into_iter().collect::<Vec<_>>();
+    for pull in &outcome.pulls {
+        let pulled_color = team_color(pull.team, HINT);
+        draw_stone(to_tile(pull.from), pulled_color, board_rect, grid);
+        outlined.push(pull.to);
+    }
+    if let Some(lifted) = outcome.lifted {
+        draw_stone(
+            to_tile(lifted),
+            team_color(outcome.team, HINT),
+            board_rect,
+            grid,
+        );
+    }
+    for coord in outlined {
+        outline_tile(to_tile(coord), board_rect, grid, 4.0, LAST_MOVE);
+    }
+}
+
+/// Draws the stone being moved under the mouse, with the size of a tile.
+fn draw_dragged_stone(mouse: Vec2, color: Color, board_rect: Rect, board: &Board) {
+    let grid = Grid::of(board);
+    let rect = tile_rect(IVec2::ZERO, board_rect, grid);
+    if grid.hexagonal {
+        draw_poly(mouse.x, mouse.y, 6, rect.h * 0.5, 90.0, color);
+    } else {
+        draw_rectangle(
+            mouse.x - rect.w * 0.5,
+            mouse.y - rect.h * 0.5,
+            rect.w,
+            rect.h,
+            color,
+        );
+    }
+}
+
+/// Shows which stones would be pulled by playing the move, with the current rules.
+fn draw_pull_preview(board: &Board, mv: Move, color: Color, board_rect: Rect) {
+    if let Some(outcome) = board.preview(mv) {
+        let grid = Grid::of(board);
+        for pull in outcome.pulls {
+            outline_tile(to_tile(pull.from), board_rect, grid, 4.0, color);
+            draw_stone(to_tile(pull.to), color, board_rect, grid);
+        }
+    }
+}
+
+/// The rect of a square tile, or the bounding box of a hexagonal one.
+fn tile_rect(tile: IVec2, board_rect: Rect, grid: Grid) -> Rect {
+    if grid.hexagonal {
+        let (radius, first_center) = hex_layout(board_rect, grid);
+        let center = first_center
+            + Vec2::new(
+                SQRT_3 * radius * (tile.x as f32 + tile.y as f32 * 0.5),
+                1.5 * radius * tile.y as f32,
+            );
+        let (w, h) = (SQRT_3 * radius, 2.0 * radius);
+        Rect::new(center.x - w * 0.5, center.y - h * 0.5, w, h)
+    } else {
+        let tile_size_x = board_rect.w / grid.size_columns as f32;
+        let tile_size_y = board_rect.h / grid.size_rows as f32;
+        Rect::new(
+            board_rect.x + tile.x as f32 * tile_size_x,
+            board_rect.y + tile.y as f32 * tile_size_y,
+            tile_size_x,
+            tile_size_y,
+        )
+    }
+}
+fn team_color(team: Team, alpha: f32) -> Color {
+    let (r, g, b) = match team {
+        Team::Empty => return TRANSPARENT,
+        Team::White => (1.0, 1.0, 1.0),
+        Team::Black => (0.0, 0.0, 0.0),
+        Team::Red => (0.8, 0.1, 0.1),
+        Team::Green => (0.1, 0.6, 0.2),
+    };
+    Color::new(r, g, b, alpha)
+}
+
+/// Spreads the scores over the width of the board. With 2 teams, black is on the left and white
+/// on the right.
+/// Above the scores, a hint in the colour of the team in turn tells whether the bot is thinking
+/// or the team has to place its second stone.
+fn draw_score(board_rect: Rect, board: &Board, thinking: bool) {
+    let scores = board.scores();
     let font_size = score_font_size(screen_width(), screen_height());
-    let white_dimensions = measure_text(&white_str, None, font_size as u16, 1.0);
-    let height = (board_rect.y - 1.0 * white_dimensions.height).round();
-    draw_text(
-        &white_str,
-        (board_rect.right() - white_dimensions.width).round(),
-        height,
-        font_size,
-        WHITE,
+    let hint = if thinking {
+        Some("thinking...")
+    } else if board.is_second_placement() {
+        Some("second stone")
+    } else {
+        None
+    };
+    if let Some(text) = hint {
+        let hint_font_size = choose_font_size(screen_width(), screen_height()) * 1.5;
+        let dimensions = measure_text(text, None, hint_font_size as u16, 1.0);
+        draw_text(
+            text,
+            (board_rect.center().x - dimensions.width * 0.5).round(),
+            (board_rect.y - dimensions.height).round(),
+            hint_font_size,
+            team_color(board.turn, 1.0),
+        );
+    }
+    let last = (scores.len() - 1) as f32;
+    for (i, (team, score)) in scores.iter().rev().enumerate() {
+        let text = score.to_string();
+        let dimensions = measure_text(&text, None, font_size as u16, 1.0);
+        let x = board_rect.left() + (board_rect.w - dimensions.width) * i as f32 / last;
+        let y = board_rect.y - 1.0 * dimensions.height;
+        draw_text(
+            &text,
+            x.round(),
+            y.round(),
+            font_size,
+            team_color(*team, 1.0),
+        );
+    }
+}
+
+fn draw_game_over(board_rect: Rect, board: &Board, result: GameResult, buttons: &GameOverButtons) {
+    draw_rectangle(
+        board_rect.x,
+        board_rect.y,
+        board_rect.w,
+        board_rect.h,
+        OVERLAY,
     );
-    let black_str = format!("{}", blacks);
-    draw_text(
-        &black_str,
-        board_rect.left().round(),
-        height,
-        font_size,
-        BLACK,
+    let description = match result {
+        GameResult::Win(team) => format!("{} wins", capitalize(team.name())),
+        GameResult::Draw => "Draw".to_string(),
+        GameResult::Resignation(team) => format!("{} resigned", capitalize(team.name())),
+    };
+    let scores = board.scores();
+    let scores = scores.iter().map(|(_, score)| score.to_string());
+    let font_size = choose_font_size(screen_width(), screen_height()) * 1.5;
+    let text = format!(
+        "{}, {}",
+        description,
+        scores.collect::<Vec<_>>().join(" to ")
     );
+    let anchor = Anchor::bottom_center(board_rect.center().x, buttons.rematch.rect().y);
+    TextRect::new(&text, anchor, font_size).render_text(WHITE);
+    render_button_flat(&buttons.rematch, &STYLE);
+    render_button_flat(&buttons.menu, &STYLE);
+    render_button_flat(&buttons.review, &STYLE);
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
 }
 
 fn score_font_size(screen_w: f32, screen_h: f32) -> f32 {
@@ -444,6 +1315,15 @@ fn draw_instructions(buttons: &Buttons) {
     buttons.restart.render(&STYLE);
     // draw_rect_lines(text_border(&buttons.restart.text_rect), 2.0, macroquad::prelude::RED);
     buttons.undo.render(&STYLE);
+    buttons.redo.render(&STYLE);
+    render_button_flat(&buttons.resign, &STYLE);
+    render_button_flat(&buttons.pass, &STYLE);
+    render_button_flat(&buttons.topology, &STYLE);
+    if let Some(polarity) = &buttons.polarity {
+        render_button_flat(polarity, &STYLE);
+    }
+    render_button_flat(&buttons.save, &STYLE);
+    render_button_flat(&buttons.load, &STYLE);
     // draw_rect_lines(text_border(&buttons.undo.text_rect), 2.0, macroquad::prelude::RED);
     // buttons.toggle_shadows.render(&STYLE);
 }
@@ -458,6 +1338,22 @@ fn text_border(rect: &TextRect) -> Rect {
     )
 }
 
+fn draw_editor_buttons(buttons: &Buttons) {
+    render_button_flat(&buttons.edit_map, &STYLE);
+    if let Some(tool) = &buttons.tool {
+        render_button_flat(tool, &STYLE);
+    }
+}
+
+fn draw_variations(panel: &VariationPanel) {
+    panel.title.render_text(DARKGRAY);
+    render_button_flat(&panel.promote, &STYLE);
+    render_button_flat(&panel.delete, &STYLE);
+    for button in &panel.continuations {
+        render_button_flat(button, &STYLE);
+    }
+}
+
 fn draw_size(buttons: &Buttons) {
     buttons.rows.render(&STYLE);
     buttons.columns.render(&STYLE);
@@ -467,11 +1363,19 @@ fn draw_size(buttons: &Buttons) {
 mod tests {
     use super::*;
 
+    fn square(size_rows: i32, size_columns: i32) -> Grid {
+        Grid {
+            size_rows,
+            size_columns,
+            hexagonal: false,
+        }
+    }
+
     #[test]
     fn test_get_tile_outside() {
         let tile = get_tile(
             Rect::new(20.0, 40.0, 100.0, 120.0),
-            (8, 8),
+            square(8, 8),
             Vec2::new(0.0, 0.0),
         );
         assert_eq!(tile, None);
@@ -481,46 +1385,37 @@ mod tests {
     fn test_get_tile_inside() {
         let tile = get_tile(
             Rect::new(20.0, 40.0, 80.0, 80.0),
-            (8, 8),
+            square(8, 8),
             Vec2::new(45.0, 55.0),
         );
         assert_eq!(tile, Some(IVec2::new(2, 1)));
     }
 
     #[test]
-    fn test_put_stone_basic() {
-        let mut _board = Board::new(5, 5);
-        let mut board = _board.board;
-        board[1][2] = Team::Black;
-        let mut current_turn = Team::White;
-        try_put_stone(&mut current_turn, &mut board, IVec2::new(3, 2));
-        assert_eq!(current_turn, Team::Black);
-        assert_eq!(board[1][2], Team::Empty);
-        assert_eq!(board[2][2], Team::White);
-        assert_eq!(board[3][2], Team::White);
-    }
-
-    #[test]
-    fn test_put_stone_toroid() {
-        let mut _board = Board::new(5, 5);
-        let mut board = _board.board;
-        board[1][2] = Team::Black;
-        let mut current_turn = Team::White;
-        try_put_stone(&mut current_turn, &mut board, IVec2::new(4, 2));
-        assert_eq!(current_turn, Team::Black);
-        assert_eq!(board[1][2], Team::Empty);
-        assert_eq!(board[0][2], Team::White);
-        assert_eq!(board[4][2], Team::White);
+    fn test_get_tile_not_square() {
+        // 2 rows and 4 columns of 10x10 tiles
+        let tile = get_tile(
+            Rect::new(0.0, 0.0, 40.0, 20.0),
+            square(2, 4),
+            Vec2::new(35.0, 5.0),
+        );
+        assert_eq!(tile, Some(IVec2::new(3, 0)));
     }
 
     #[test]
-    fn test_can_not_put_stone() {
-        let mut _board = Board::new(5, 5);
-        let mut board = _board.board;
-        board[1][2] = Team::Black;
-        let mut current_turn = Team::White;
-        try_put_stone(&mut current_turn, &mut board, IVec2::new(1, 2));
-        assert_eq!(current_turn, Team::White);
-        assert_eq!(board[1][2], Team::Black);
+    fn test_get_tile_hexagonal() {
+        let grid = Grid {
+            size_rows: 3,
+            size_columns: 4,
+            hexagonal: true,
+        };
+        let board_rect = Rect::new(10.0, 20.0, 300.0, 200.0);
+        for tile in grid.tiles() {
+            let center = tile_rect(tile, board_rect, grid).center();
+            assert_eq!(get_tile(board_rect, grid, center), Some(tile));
+        }
+        // the rhombus leans to the right, so the bottom left corner is empty
+        let corner = Vec2::new(board_rect.left() + 1.0, board_rect.bottom() - 1.0);
+        assert_eq!(get_tile(board_rect, grid, corner), None);
     }
 }
@@ -5,6 +5,7 @@ use juquad::widgets::text::TextRect;
 use macroquad::color::WHITE;
 use macroquad::prelude::{next_frame, screen_height, screen_width, FilterMode, Texture2D};
 
+#[derive(Copy, Clone)]
 pub struct Textures {
     pub restart: Texture2D,
     pub undo: Texture2D,
@@ -1,3 +1,6 @@
+use crate::board::Rules;
+use crate::bot_player::BotKind;
+use crate::scenes::settings::{self, handicap_text, next_handicap};
 use crate::{choose_font_size, render_button_flat, FONT, STYLE};
 use juquad::widgets::anchor::Anchor;
 use juquad::widgets::button::Button;
@@ -6,24 +9,27 @@ use macroquad::color::GRAY;
 use macroquad::input::{is_key_pressed, KeyCode};
 use macroquad::prelude::{clear_background, next_frame, screen_height, screen_width};
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub enum Player {
     Local,
+    Ai(BotKind),
     Server,
     Client,
 }
 
-pub async fn scene() -> Option<Player> {
+/// The rules can be changed in the settings, and stay changed for the next time the menu is shown.
+pub async fn scene(enable_networking: bool, rules: &mut Rules) -> Option<Player> {
     let mut width = screen_width();
     let mut height = screen_height();
-    let (mut _font_size, mut buttons) = reset(width, height);
+    let mut bot_kind = BotKind::AlphaBeta;
+    let (mut _font_size, mut buttons) = reset(width, height, enable_networking, bot_kind, rules);
     loop {
         let new_width = screen_width();
         let new_height = screen_height();
         if new_width != width || new_height != height {
             width = new_width;
             height = new_height;
-            (_font_size, buttons) = reset(width, height);
+            (_font_size, buttons) = reset(width, height, enable_networking, bot_kind, rules);
         }
         if is_key_pressed(KeyCode::Escape) || buttons.exit.interact().is_clicked() {
             return None;
@@ -31,11 +37,30 @@ pub async fn scene() -> Option<Player> {
         if buttons.local.interact().is_clicked() {
             return Some(Player::Local);
         }
-        if buttons.serve.interact().is_clicked() {
-            return Some(Player::Server);
+        if buttons.ai.interact().is_clicked() {
+            return Some(Player::Ai(bot_kind));
         }
-        if buttons.connect.interact().is_clicked() {
-            return Some(Player::Client);
+        if buttons.bot_kind.interact().is_clicked() {
+            bot_kind = bot_kind.next();
+            (_font_size, buttons) = reset(width, height, enable_networking, bot_kind, rules);
+        }
+        if buttons.handicap.interact().is_clicked() {
+            rules.handicap = next_handicap(rules.handicap);
+            (_font_size, buttons) = reset(width, height, enable_networking, bot_kind, rules);
+        }
+        if buttons.settings.interact().is_clicked() {
+            *rules = settings::scene(*rules).await;
+            (_font_size, buttons) = reset(width, height, enable_networking, bot_kind, rules);
+        }
+        if let Some(serve) = buttons.serve.as_mut() {
+            if serve.interact().is_clicked() {
+                return Some(Player::Server);
+            }
+        }
+        if let Some(connect) = buttons.connect.as_mut() {
+            if connect.interact().is_clicked() {
+                return Some(Player::Client);
+            }
         }
         clear_background(GRAY);
         buttons.render();
@@ -43,39 +68,100 @@ pub async fn scene() -> Option<Player> {
     }
 }
 
-fn reset(width: f32, height: f32) -> (f32, Buttons) {
+fn reset(
+    width: f32,
+    height: f32,
+    enable_networking: bool,
+    bot_kind: BotKind,
+    rules: &Rules,
+) -> (f32, Buttons) {
     let font_size = choose_font_size(width, height) * 2.0;
-    let buttons = create_button_group(font_size, width, height);
+    let buttons = create_button_group(font_size, width, height, enable_networking, bot_kind, rules);
     (font_size, buttons)
 }
 
 pub struct Buttons {
     pub local: Button,
-    pub connect: Button,
-    pub serve: Button,
+    pub ai: Button,
+    pub bot_kind: Button,
+    pub handicap: Button,
+    pub settings: Button,
+    pub connect: Option<Button>,
+    pub serve: Option<Button>,
     pub exit: Button,
 }
 impl Buttons {
     pub fn render(&self) {
-        render_button_flat(&self.local, &STYLE); 
-        render_button_flat(&self.connect, &STYLE);
-        render_button_flat(&self.serve, &STYLE);
+        render_button_flat(&self.local, &STYLE);
+        render_button_flat(&self.ai, &STYLE);
+        render_button_flat(&self.bot_kind, &STYLE);
+        render_button_flat(&self.handicap, &STYLE);
+        render_button_flat(&self.settings, &STYLE);
+        if let Some(connect) = &self.connect {
+            render_button_flat(connect, &STYLE);
+        }
+        if let Some(serve) = &self.serve {
+            render_button_flat(serve, &STYLE);
+        }
         render_button_flat(&self.exit, &STYLE);
     }
 }
 
-fn create_button_group(font_size: f32, width: f32, height: f32) -> Buttons {
+fn create_button_group(
+    font_size: f32,
+    width: f32,
+    height: f32,
+    enable_networking: bool,
+    bot_kind: BotKind,
+    rules: &Rules,
+) -> Buttons {
+    let bot_kind_text = format!("Computer: {}", bot_kind.name());
+    let handicap_text = handicap_text(rules.handicap);
     let button_group = ButtonGroup::new_with_font(
         font_size,
         unsafe { FONT },
         Anchor::top_center(width * 0.5, height * 0.25),
     );
-    let [local, connect, serve, exit] =
-        button_group.create(["Local game", "Connect to server", "Serve game", "Exit"]);
-    Buttons {
-        local,
-        connect,
-        serve,
-        exit,
+    if enable_networking {
+        let [local, ai, bot_kind, handicap, settings, connect, serve, exit] =
+            button_group.create([
+                "Local game",
+                "Play against computer",
+                &bot_kind_text,
+                &handicap_text,
+                "Settings",
+                "Connect to server",
+                "Serve game",
+                "Exit",
+            ]);
+        Buttons {
+            local,
+            ai,
+            bot_kind,
+            handicap,
+            settings,
+            connect: Some(connect),
+            serve: Some(serve),
+            exit,
+        }
+    } else {
+        let [local, ai, bot_kind, handicap, settings, exit] = button_group.create([
+            "Local game",
+            "Play against computer",
+            &bot_kind_text,
+            &handicap_text,
+            "Settings",
+            "Exit",
+        ]);
+        Buttons {
+            local,
+            ai,
+            bot_kind,
+            handicap,
+            settings,
+            connect: None,
+            serve: None,
+            exit,
+        }
     }
 }
@@ -0,0 +1,215 @@
+use crate::board::{Reach, Rules, TurnOrder, WinCondition, MAX_HANDICAP, MAX_KOMI, MAX_TEAMS};
+use crate::{choose_font_size, render_button_flat, FONT, STYLE};
+use juquad::widgets::anchor::Anchor;
+use juquad::widgets::button::Button;
+use juquad::widgets::button_group::ButtonGroup;
+use juquad::widgets::Widget;
+use macroquad::color::GRAY;
+use macroquad::input::{is_key_pressed, KeyCode};
+use macroquad::prelude::{clear_background, next_frame, screen_height, screen_width};
+
+/// Lets the player choose the rules of the next games. Returns the chosen rules when leaving.
+pub async fn scene(mut rules: Rules) -> Rules {
+    let mut width = screen_width();
+    let mut height = screen_height();
+    let (mut _font_size, mut buttons) = reset(width, height, &rules);
+    clear_background(GRAY);
+    next_frame().await; // ignore last click
+    loop {
+        let new_width = screen_width();
+        let new_height = screen_height();
+        if new_width != width || new_height != height {
+            width = new_width;
+            height = new_height;
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if is_key_pressed(KeyCode::Escape) || buttons.back.interact().is_clicked() {
+            return rules;
+        }
+        if buttons.reach.interact().is_clicked() {
+            rules.reach = rules.reach.next();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.magnetism.interact().is_clicked() {
+            rules.magnetism = rules.magnetism.next();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.polarity.interact().is_clicked() {
+            rules.polarity = rules.polarity.next();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.repetition.interact().is_clicked() {
+            rules.repetition = rules.repetition.next();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.players.interact().is_clicked() {
+            let count = rules.turn_order.teams().len() % MAX_TEAMS + 1;
+            rules.turn_order = TurnOrder::with_count(count.max(2)).unwrap();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.turn_order.interact().is_clicked() {
+            rules.turn_order = rules.turn_order.next_permutation();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.pie.interact().is_clicked() {
+            rules.pie = !rules.pie;
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.komi.interact().is_clicked() {
+            rules.komi = (rules.komi + 1) % (MAX_KOMI + 1);
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.handicap.interact().is_clicked() {
+            rules.handicap = next_handicap(rules.handicap);
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.win.interact().is_clicked() {
+            rules.win = rules.win.next();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.stones_per_turn.interact().is_clicked() {
+            rules.stones_per_turn = rules.stones_per_turn.next();
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        if buttons.relocation.interact().is_clicked() {
+            rules.relocation = !rules.relocation;
+            (_font_size, buttons) = reset(width, height, &rules);
+        }
+        clear_background(GRAY);
+        buttons.render();
+        next_frame().await
+    }
+}
+
+fn reset(width: f32, height: f32, rules: &Rules) -> (f32, Buttons) {
+    let font_size = choose_font_size(width, height) * 2.0;
+    let buttons = create_button_group(font_size, width, height, rules);
+    (font_size, buttons)
+}
+
+pub struct Buttons {
+    pub reach: Button,
+    pub magnetism: Button,
+    pub polarity: Button,
+    pub repetition: Button,
+    pub players: Button,
+    pub turn_order: Button,
+    pub pie: Button,
+    pub komi: Button,
+    pub handicap: Button,
+    pub win: Button,
+    pub stones_per_turn: Button,
+    pub relocation: Button,
+    pub back: Button,
+}
+impl Buttons {
+    pub fn render(&self) {
+        render_button_flat(&self.reach, &STYLE);
+        render_button_flat(&self.magnetism, &STYLE);
+        render_button_flat(&self.polarity, &STYLE);
+        render_button_flat(&self.repetition, &STYLE);
+        render_button_flat(&self.players, &STYLE);
+        render_button_flat(&self.turn_order, &STYLE);
+        render_button_flat(&self.pie, &STYLE);
+        render_button_flat(&self.komi, &STYLE);
+        render_button_flat(&self.handicap, &STYLE);
+        render_button_flat(&self.win, &STYLE);
+        render_button_flat(&self.stones_per_turn, &STYLE);
+        render_button_flat(&self.relocation, &STYLE);
+        render_button_flat(&self.back, &STYLE);
+    }
+}
+
+fn create_button_group(font_size: f32, width: f32, height: f32, rules: &Rules) -> Buttons {
+    let reach_text = reach_text(rules.reach);
+    let magnetism_text = format!("Magnetism: {}", rules.magnetism.name());
+    let polarity_text = format!("Polarity: {}", rules.polarity.name());
+    let repetition_text = format!("Repeated positions: {}", rules.repetition.name());
+    let win_text = win_text(rules.win);
+    let stones_per_turn_text = format!("Stones per turn: {}", rules.stones_per_turn.name());
+    let relocation_text = format!(
+        "Move stones when 1 cell is left: {}",
+        on_off(rules.relocation)
+    );
+    let game_rules = ButtonGroup::new_with_font(
+        font_size,
+        unsafe { FONT },
+        Anchor::top_center(width * 0.5, height * 0.1),
+    );
+    let [reach, magnetism, polarity, repetition, win, stones_per_turn, relocation] = game_rules
+        .create([
+            reach_text.as_str(),
+            &magnetism_text,
+            &polarity_text,
+            &repetition_text,
+            &win_text,
+            &stones_per_turn_text,
+            &relocation_text,
+        ]);
+
+    let players_text = format!("Players: {}", rules.turn_order.teams().len());
+    let turn_order_text = format!("Turn order: {}", rules.turn_order.name());
+    let pie_text = format!("Pie rule: {}", on_off(rules.pie));
+    let komi_text = format!("Komi: {}", rules.komi);
+    let handicap_text = handicap_text(rules.handicap);
+    let players_top = Widget::rect(&relocation).bottom() + font_size;
+    let players_rules = ButtonGroup::new_with_font(
+        font_size,
+        unsafe { FONT },
+        Anchor::top_center(width * 0.5, players_top),
+    );
+    let [players, turn_order, pie, komi, handicap, back] = players_rules.create([
+        players_text.as_str(),
+        &turn_order_text,
+        &pie_text,
+        &komi_text,
+        &handicap_text,
+        "Back",
+    ]);
+    Buttons {
+        reach,
+        magnetism,
+        polarity,
+        repetition,
+        players,
+        turn_order,
+        pie,
+        komi,
+        handicap,
+        win,
+        stones_per_turn,
+        relocation,
+        back,
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn reach_text(reach: Reach) -> String {
+    match reach {
+        Reach::Unlimited => "Magnet reach: unlimited".to_string(),
+        Reach::Cells(cells) => format!("Magnet reach: {} cells", cells),
+    }
+}
+
+fn win_text(win: WinCondition) -> String {
+    match win {
+        WinCondition::Majority => "Win: most stones".to_string(),
+        WinCondition::Stones(stones) => format!("Win: first to {} stones", stones),
+        WinCondition::Line(length) => format!("Win: first line of {}", length),
+    }
+}
+
+pub fn handicap_text(handicap: usize) -> String {
+    format!("Handicap: {} stones", handicap)
+}
+
+pub fn next_handicap(handicap: usize) -> usize {
+    (handicap + 1) % (MAX_HANDICAP + 1)
+}
@@ -12,6 +12,7 @@ pub struct ComplexButton {
     pub rect: Rect,
     pub icon_rect: Rect,
     pub icon_at_rest: Vec<Texture2D>,
+    pub flip_icon: bool,
     // pub icon_hovered: Vec<Texture2D>,
     // pub icon_pressed: Vec<Texture2D>,
     pub text: TextRect,
@@ -36,6 +37,7 @@ impl ComplexButton {
             rect,
             icon_rect,
             icon_at_rest: textures,
+            flip_icon: false,
             text,
             base: ButtonBase {
                 interaction: Interaction::None,
@@ -43,6 +45,12 @@ impl ComplexButton {
             },
         }
     }
+
+    /// Draws the icon mirrored horizontally, e.g. to get a redo arrow from the undo one.
+    pub fn with_flipped_icon(mut self) -> Self {
+        self.flip_icon = true;
+        self
+    }
 }
 
 impl ButtonTrait for ComplexButton {
@@ -56,6 +64,7 @@ impl ButtonTrait for ComplexButton {
                 style.text_color,
                 DrawTextureParams {
                     dest_size: Some(self.icon_rect.size()),
+                    flip_x: self.flip_icon,
                     ..Default::default()
                 },
             );
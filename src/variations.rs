@@ -0,0 +1,187 @@
+//! Tree of the moves tried from each position, so that undoing and playing another move keeps
+//! the line that was undone. The first continuation of each position is its main line.
+
+use crate::board::{Move, MoveOutcome};
+
+#[derive(Clone, Default)]
+pub struct Variations {
+    root: Node,
+    /// Index of the chosen continuation at each position, from the start of the game to the
+    /// current position.
+    path: Vec<usize>,
+}
+
+#[derive(Clone, Default)]
+struct Node {
+    children: Vec<Child>,
+    /// The continuation that redo follows, which is the last one visited.
+    selected: usize,
+}
+
+#[derive(Clone)]
+struct Child {
+    mv: Move,
+    /// What the move changed, to undo it.
+    outcome: MoveOutcome,
+    node: Node,
+}
+
+impl Variations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets all the moves.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Goes to the continuation with this move, creating it if it didn't exist yet.
+    pub fn push(&mut self, mv: Move, outcome: MoveOutcome) {
+        let node = self.current_mut();
+        let index = match node.children.iter().position(|child| child.mv == mv) {
+            Some(index) => index,
+            None => {
+                node.children.push(Child {
+                    mv,
+                    outcome,
+                    node: Node::default(),
+                });
+                node.children.len() - 1
+            }
+        };
+        node.selected = index;
+        self.path.push(index);
+    }
+
+    /// Goes back one move, keeping it as a continuation, and returns what it changed.
+    pub fn undo(&mut self) -> Option<MoveOutcome> {
+        let index = self.path.pop()?;
+        let node = self.current_mut();
+        node.selected = index;
+        Some(node.children[index].outcome.clone())
+    }
+
+    /// What the move that led to the current position changed.
+    pub fn last_outcome(&self) -> Option<&MoveOutcome> {
+        let (last, parent_path) = self.path.split_last()?;
+        let mut node = &self.root;
+        for index in parent_path {
+            node = &node.children[*index].node;
+        }
+        Some(&node.children[*last].outcome)
+    }
+
+    /// The move that redo would play.
+    pub fn next_move(&self) -> Option<Move> {
+        let node = self.current();
+        node.children.get(node.selected).map(|child| child.mv)
+    }
+
+    /// The moves already tried from the current position, the main line first.
+    pub fn continuations(&self) -> Vec<Move> {
+        self.current()
+            .children
+            .iter()
+            .map(|child| child.mv)
+            .collect()
+    }
+
+    /// The moves from the start of the game to the current position.
+    pub fn moves(&self) -> Vec<Move> {
+        let mut node = &self.root;
+        let mut moves = Vec::new();
+        for index in &self.path {
+            let child = &node.children[*index];
+            moves.push(child.mv);
+            node = &child.node;
+        }
+        moves
+    }
+
+    pub fn is_main_line(&self) -> bool {
+        self.path.iter().all(|index| *index == 0)
+    }
+
+    /// Makes the line that leads to the current position the main line.
+    pub fn promote(&mut self) {
+        let mut node = &mut self.root;
+        for index in &mut self.path {
+            let child = node.children.remove(*index);
+            node.children.insert(0, child);
+            *index = 0;
+            node.selected = 0;
+            node = &mut node.children[0].node;
+        }
+    }
+
+    /// Removes the last move and all its continuations, and returns what the move changed.
+    pub fn delete(&mut self) -> Option<MoveOutcome> {
+        let index = self.path.pop()?;
+        let node = self.current_mut();
+        let child = node.children.remove(index);
+        node.selected = 0;
+        Some(child.outcome)
+    }
+
+    fn current(&self) -> &Node {
+        let mut node = &self.root;
+        for index in &self.path {
+            node = &node.children[*index].node;
+        }
+        node
+    }
+
+    fn current_mut(&mut self) -> &mut Node {
+        let mut node = &mut self.root;
+        for index in &self.path {
+            node = &mut node.children[*index].node;
+        }
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{place, Coord, Team};
+
+    fn push(variations: &mut Variations, x: i32, y: i32) {
+        let outcome = MoveOutcome {
+            team: Team::White,
+            placed: Some(Coord::new(x, y)),
+            pulls: Vec::new(),
+            previous_passes: 0,
+            swapped: false,
+            second_placement: false,
+            lifted: None,
+            previous_vacated: Vec::new(),
+        };
+        variations.push(place(x, y), outcome);
+    }
+
+    #[test]
+    fn test_branches() {
+        let mut variations = Variations::new();
+        push(&mut variations, 0, 0);
+        push(&mut variations, 1, 1);
+        variations.undo().unwrap();
+        push(&mut variations, 2, 2);
+        assert!(!variations.is_main_line());
+        variations.undo().unwrap();
+        assert_eq!(variations.continuations(), vec![place(1, 1), place(2, 2)]);
+        assert_eq!(variations.next_move(), Some(place(2, 2)));
+
+        push(&mut variations, 2, 2);
+        variations.promote();
+        assert!(variations.is_main_line());
+        assert_eq!(variations.moves(), vec![place(0, 0), place(2, 2)]);
+        variations.undo().unwrap();
+        assert_eq!(variations.continuations(), vec![place(2, 2), place(1, 1)]);
+
+        push(&mut variations, 1, 1);
+        variations.delete().unwrap();
+        assert_eq!(variations.continuations(), vec![place(2, 2)]);
+        assert_eq!(variations.moves(), vec![place(0, 0)]);
+    }
+}
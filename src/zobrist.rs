@@ -0,0 +1,56 @@
+//! Zobrist hashing of positions. Each stone in each cell and each team in turn has a random
+//! 64-bit key, and the hash of a position is the XOR of the keys of its stones and turn, so it
+//! can also be updated incrementally when a stone moves.
+//!
+//! The keys are derived from the cell and the team instead of stored in a table, so that they
+//! are the same for every board size and on every machine, and two players can compare hashes.
+
+use crate::board::{Board2d, Coord, Team};
+
+pub fn hash(board: &Board2d, turn: Team) -> u64 {
+    let mut hash = turn_key(turn);
+    for (x, column) in board.iter().enumerate() {
+        for (y, team) in column.iter().enumerate() {
+            if *team != Team::Empty {
+                hash ^= cell_key(Coord::new(x as i32, y as i32), *team);
+            }
+        }
+    }
+    hash
+}
+
+/// The key of a stone of the team in the cell.
+pub fn cell_key(coord: Coord, team: Team) -> u64 {
+    splitmix64((coord.x as u64) << 40 | (coord.y as u64) << 8 | team as u64)
+}
+
+/// The key of the team in turn.
+pub fn turn_key(team: Team) -> u64 {
+    splitmix64(1 << 63 | team as u64)
+}
+
+/// Scrambles the bits of the input, see https://prng.di.unimi.it/splitmix64.c
+fn splitmix64(input: u64) -> u64 {
+    let mut z = input.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        let mut board = vec![vec![Team::Empty; 3]; 2];
+        let empty = hash(&board, Team::White);
+        assert_ne!(empty, hash(&board, Team::Black));
+        board[1][2] = Team::Black;
+        let with_stone = hash(&board, Team::White);
+        assert_ne!(with_stone, empty);
+        assert_eq!(with_stone ^ cell_key(Coord::new(1, 2), Team::Black), empty);
+        board[1][2] = Team::White;
+        assert_ne!(hash(&board, Team::White), with_stone);
+    }
+}